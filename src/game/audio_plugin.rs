@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use bevy::app::Events;
+use bevy::prelude::*;
+
+use crate::components::{AppState, EnemyKilledEvent, PlayerHitEvent, Sound, SoundEvent};
+use crate::loading_plugin::GameAssets;
+use crate::settings_plugin::Settings;
+
+// Plugin
+//Named GameAudioPlugin not to be confused with bevy's AudioPlugin.
+//When bevy's AudioPlugin is disabled (no audio device), every system here does nothing.
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        //GameCorePlugin adds it too, for the parts sending sounds without this plugin
+        if !app.world_mut().contains_resource::<Events<SoundEvent>>() {
+            app.add_event::<SoundEvent>();
+        }
+        app.init_resource::<GameAudio>()
+            .init_resource::<MusicState>()
+            .add_system(sound_effect_system.system())
+            .add_system(music_system.system());
+    }
+    fn name(&self) -> &str {
+        "GameAudioPlugin"
+    }
+}
+
+//Volumes in [0..1] for each category, part of Settings
+#[derive(Clone)]
+pub struct AudioSettings {
+    //false to never open the audio device
+    pub enabled: bool,
+    pub master: f32,
+    pub sfx: f32,
+    pub music: f32,
+}
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            enabled: true,
+            master: 0.8,
+            sfx: 1.0,
+            music: 0.6,
+        }
+    }
+}
+impl AudioSettings {
+    pub fn volume(&self, sound: Sound) -> f32 {
+        match sound {
+            Sound::TitleMusic | Sound::GameMusic => self.master * self.music,
+            _ => self.master * self.sfx,
+        }
+    }
+}
+
+//true if sounds can be played on this machine
+pub fn audio_device_available(settings: &AudioSettings) -> bool {
+    settings.enabled && rodio::OutputStream::try_default().is_ok()
+}
+
+//length of the music files, they are played again every loop
+const MUSIC_LOOP_SECONDS: f64 = 2.0;
+
+fn sound_path(sound: Sound) -> &'static str {
+    match sound {
+        Sound::Shot => "sounds/shot.wav",
+        Sound::Explosion => "sounds/explosion.wav",
+        Sound::PlayerHit => "sounds/hit.wav",
+        Sound::Pickup => "sounds/pickup.wav",
+        Sound::TitleMusic => "sounds/title.wav",
+        Sound::GameMusic => "sounds/game.wav",
+    }
+}
+
+const SOUNDS: [Sound; 6] = [
+    Sound::Shot,
+    Sound::Explosion,
+    Sound::PlayerHit,
+    Sound::Pickup,
+    Sound::TitleMusic,
+    Sound::GameMusic,
+];
+
+//Loaded sounds and copies with the volume applied.
+//bevy's Audio can't change the volume, so the samples themselves are scaled.
+#[derive(Default)]
+pub struct GameAudio {
+    sources: HashMap<Sound, Handle<AudioSource>>,
+    //key: sound, gain at the start and at the end in percent
+    scaled: HashMap<(Sound, u32, u32), Handle<AudioSource>>,
+}
+
+impl FromWorld for GameAudio {
+    fn from_world(world: &mut World) -> Self {
+        let mut audio = GameAudio::default();
+        //no loader for audio files without bevy's AudioPlugin
+        if world.get_resource::<Audio>().is_none() {
+            return audio;
+        }
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        for sound in SOUNDS.iter() {
+            audio.sources.insert(*sound, asset_server.load(sound_path(*sound)));
+        }
+        //the loading screen waits for the sounds too, and reports the missing ones
+        if let Some(mut assets) = world.get_resource_mut::<GameAssets>() {
+            for sound in SOUNDS.iter() {
+                assets.add_to_manifest(sound_path(*sound), audio.sources[sound].id);
+            }
+        }
+        audio
+    }
+}
+
+impl GameAudio {
+    //play a sound with the gain changing linearly from start to end
+    fn play(
+        &mut self,
+        audio: &Audio,
+        sources: &mut Assets<AudioSource>,
+        sound: Sound,
+        start: f32,
+        end: f32,
+    ) {
+        let original = match self.sources.get(&sound) {
+            Some(handle) => handle.clone(),
+            None => return,
+        };
+        //quantize to limit the number of copies
+        let start = (start.max(0.0).min(1.0) * 20.0).round() as u32 * 5;
+        let end = (end.max(0.0).min(1.0) * 20.0).round() as u32 * 5;
+        if start == 0 && end == 0 {
+            return;
+        }
+        if start == 100 && end == 100 {
+            audio.play(original);
+            return;
+        }
+        if let Some(handle) = self.scaled.get(&(sound, start, end)) {
+            audio.play(handle.clone());
+            return;
+        }
+        //not loaded yet, skip it
+        let bytes = match sources.get(&original) {
+            Some(source) => apply_gain(&source.bytes, start as f32 / 100.0, end as f32 / 100.0),
+            None => return,
+        };
+        //not a 16bit PCM wav, play it as it is
+        let handle = match bytes {
+            Some(bytes) => sources.add(AudioSource { bytes: bytes.into() }),
+            None => original,
+        };
+        self.scaled.insert((sound, start, end), handle.clone());
+        audio.play(handle);
+    }
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+//copy a wav file multiplying samples by a gain going from start to end.
+//None if it isn't a 16bit PCM wav.
+fn apply_gain(bytes: &[u8], start: f32, end: f32) -> Option<Vec<u8>> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut pcm16 = false;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = read_u32(bytes, pos + 4) as usize;
+        let body = pos + 8;
+        if id == b"fmt " && body + 16 <= bytes.len() {
+            //format 1 is PCM
+            pcm16 = read_u16(bytes, body) == 1 && read_u16(bytes, body + 14) == 16;
+        } else if id == b"data" {
+            if !pcm16 {
+                return None;
+            }
+            let len = size.min(bytes.len() - body);
+            let samples = len / 2;
+            let mut out = bytes.to_vec();
+            for i in 0..samples {
+                let gain = start + (end - start) * i as f32 / samples as f32;
+                let at = body + i * 2;
+                let value = i16::from_le_bytes([out[at], out[at + 1]]) as f32 * gain;
+                let value = value.max(i16::MIN as f32).min(i16::MAX as f32) as i16;
+                out[at..at + 2].copy_from_slice(&value.to_le_bytes());
+            }
+            return Some(out);
+        }
+        //chunks are padded to even size
+        pos = body + size + (size & 1);
+    }
+    None
+}
+
+fn sound_effect_system(
+    audio: Option<Res<Audio>>,
+    sources: Option<ResMut<Assets<AudioSource>>>,
+    mut game_audio: ResMut<GameAudio>,
+    settings: Res<Settings>,
+    mut sound_events: EventReader<SoundEvent>,
+    mut killed_events: EventReader<EnemyKilledEvent>,
+    mut hit_events: EventReader<PlayerHitEvent>,
+) {
+    let mut sounds: Vec<Sound> = sound_events.iter().map(|e| e.0).collect();
+    sounds.extend(killed_events.iter().map(|_| Sound::Explosion));
+    sounds.extend(hit_events.iter().map(|_| Sound::PlayerHit));
+    let (audio, mut sources) = match (audio, sources) {
+        (Some(audio), Some(sources)) => (audio, sources),
+        _ => return,
+    };
+    //the same sound many times in one frame is just louder noise
+    sounds.dedup();
+    for sound in sounds {
+        let volume = settings.audio.volume(sound);
+        game_audio.play(&audio, &mut sources, sound, volume, volume);
+    }
+}
+
+//Which music is playing, used as Resource
+#[derive(Default)]
+pub struct MusicState {
+    current: Option<Sound>,
+    next_loop_at: f64,
+}
+
+//loop the music for the current scene, crossfading over one loop when the scene changes
+fn music_system(
+    audio: Option<Res<Audio>>,
+    sources: Option<ResMut<Assets<AudioSource>>>,
+    mut game_audio: ResMut<GameAudio>,
+    mut music: ResMut<MusicState>,
+    settings: Res<Settings>,
+    state: Res<State<AppState>>,
+    time: Res<Time>,
+) {
+    let (audio, mut sources) = match (audio, sources) {
+        (Some(audio), Some(sources)) => (audio, sources),
+        _ => return,
+    };
+    let now = time.seconds_since_startup();
+    if now < music.next_loop_at {
+        return;
+    }
+    let wanted = match state.current() {
+        AppState::InGame | AppState::LoadingRun => Sound::GameMusic,
+        //overlays keep the music of the screen below
+        AppState::Paused | AppState::Settings | AppState::HighScores | AppState::Console => {
+            music.current.unwrap_or(Sound::TitleMusic)
+        }
+        _ => Sound::TitleMusic,
+    };
+    let volume = settings.audio.volume(wanted);
+    match music.current {
+        Some(current) if current == wanted => {
+            game_audio.play(&audio, &mut sources, wanted, volume, volume);
+        }
+        Some(current) => {
+            game_audio.play(&audio, &mut sources, current, volume, 0.0);
+            game_audio.play(&audio, &mut sources, wanted, 0.0, volume);
+        }
+        None => {
+            game_audio.play(&audio, &mut sources, wanted, 0.0, volume);
+        }
+    }
+    music.current = Some(wanted);
+    music.next_loop_at = now + MUSIC_LOOP_SECONDS;
+}
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+
+use crate::collision_plugin::kill_enemy;
+use crate::components::{Enemy, EnemyKilledEvent, PlayerShip, Shockwave, TimeScale};
+use crate::components::AppState;
+use crate::loading_plugin::GameAssets;
+use crate::pool::Pool;
+use crate::settings_plugin::Settings;
+use crate::profiler_plugin::timed;
+
+// Plugin
+pub struct BombPlugin;
+
+impl Plugin for BombPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(timed(use_bomb_system.system()))
+                .with_system(timed(shockwave_system.system())),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "BombPlugin"
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    shockwaves: Query<Entity, With<Shockwave>>,
+) {
+    for entity in shockwaves.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+//Right click or the bomb key (Space) drops a bomb if any are left
+fn use_bomb_system(
+    mut commands: Commands,
+    mut player: Query<(&Transform, &mut PlayerShip)>,
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    settings: Res<Settings>,
+    assets: Res<GameAssets>,
+) {
+    if !mouse.just_pressed(MouseButton::Right) && !keys.just_pressed(settings.keys.bomb) {
+        return;
+    }
+    if let Some((player_tr, mut ship)) = player.iter_mut().next() {
+        if ship.bombs <= 0 {
+            return;
+        }
+        ship.bombs -= 1;
+        commands
+            .spawn()
+            .insert_bundle(SpriteBundle {
+                material: assets.shockwave_material.clone(),
+                transform: Transform::from_translation(player_tr.translation),
+                sprite: Sprite::new(Vec2::ZERO),
+                ..Default::default()
+            }).insert(
+            Shockwave::default()
+        );
+    }
+}
+
+fn shockwave_system(
+    mut commands: Commands,
+    mut shockwaves: Query<(Entity, &Transform, &mut Shockwave, &mut Sprite)>,
+    mut enemies: Query<(Entity, &Transform, &mut Enemy, &mut Visible)>,
+    mut pool: ResMut<Pool<Enemy>>,
+    mut killed_events: EventWriter<EnemyKilledEvent>,
+    time: Res<TimeScale>,
+) {
+    for (entity, tr, mut wave, mut sprite) in shockwaves.iter_mut() {
+        wave.radius += wave.speed * time.delta;
+        sprite.size = Vec2::new(wave.radius * 2., wave.radius * 2.);
+        for (e_entity, e_tr, mut enemy, mut visible) in enemies.iter_mut() {
+            //dead enemies stay in the query until the end of the frame, not to be killed twice
+            if !visible.is_visible || enemy.health <= 0 || wave.hit_enemies.contains(&e_entity) {
+                continue;
+            }
+            if tr.translation.distance(e_tr.translation) < wave.radius {
+                wave.hit_enemies.push(e_entity);
+                enemy.health -= wave.damage;
+                if enemy.health <= 0 {
+                    kill_enemy(
+                        &mut commands,
+                        &mut killed_events,
+                        &mut pool,
+                        e_entity,
+                        e_tr,
+                        &mut visible,
+                    );
+                }
+            }
+        }
+        if wave.radius >= wave.max_radius {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use crate::components::{PlayerShip, Weapon, WeaponKind, Bullet, Enemy, HIT};
+use crate::components::{AppState, Arena, AttractMode, DevOptions, Sound, SoundEvent, TimeScale};
+use crate::console_plugin::{ConsoleAppExt, ConsoleCommandEvent, ConsoleCommands, ConsoleLog};
+use crate::loading_plugin::GameAssets;
+use crate::pool::Pool;
+use crate::settings_plugin::Settings;
+use crate::profiler_plugin::timed;
+
+//
+fn fire_bullet_system(
+    mut commands: Commands,
+    mut player: Query<(&Transform, &mut Weapon, &PlayerShip)>,
+    input: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    settings: Res<Settings>,
+    mut pool: ResMut<Pool<Bullet>>,
+    assets: Res<GameAssets>,
+    mut sound_events: EventWriter<SoundEvent>,
+    attract: Res<AttractMode>,
+    options: Res<DevOptions>,
+    time: Res<TimeScale>,
+) {
+    let (player_transform, mut weapon, ship) = match player.iter_mut().next() {
+        Some(v) => v,
+        None => return,
+    };
+    //no game time passed in this frame
+    if time.ticks == 0 {
+        return;
+    }
+    if weapon.frames_to_fire > 0 {
+        weapon.frames_to_fire -= time.ticks;
+        return;
+    }
+    //the demo keeps firing
+    let pressed = input.pressed(MouseButton::Left) || keys.pressed(settings.keys.fire);
+    if !pressed && !attract.active {
+        return;
+    }
+    let mut spec = weapon.kind.spec();
+    //weapon upgrade power-up: faster and stronger shots while it lasts
+    if ship.upgrade_frames > 0 {
+        spec.frames_per_shot /= 2;
+        spec.damage += HIT;
+    }
+    weapon.frames_to_fire = (spec.frames_per_shot as f32 / options.fire_rate).round() as i32;
+    sound_events.send(SoundEvent(Sound::Shot));
+
+    //the direction the ship is facing
+    let forward = player_transform.rotation * Vec3::Y;
+    let forward = Vec2::new(forward.x, forward.y);
+    let angles: &[f32] = match weapon.kind {
+        WeaponKind::Spread => &[-0.25, 0.0, 0.25],
+        _ => &[0.0],
+    };
+    for angle in angles {
+        let dir = Quat::from_rotation_z(*angle) * Vec3::new(forward.x, forward.y, 0.);
+        let velocity = Vec2::new(dir.x, dir.y) * spec.speed;
+        let mut tr = *player_transform;
+        tr.rotation = Quat::from_rotation_z(Vec2::Y.angle_between(velocity));
+        pool.spawn(&mut commands)
+            .insert_bundle(SpriteBundle {
+                material: assets.weapon_material(weapon.kind),
+                transform: tr,
+                sprite: Sprite::new(spec.size),
+                ..Default::default()
+            }).insert(
+            Bullet {
+                kind: weapon.kind,
+                velocity,
+                damage: spec.damage,
+                piercing: weapon.kind == WeaponKind::Laser,
+                hit_enemies: Vec::new(),
+                homing: weapon.kind == WeaponKind::Homing,
+                bounces: if weapon.kind == WeaponKind::Bouncing { 3 } else { 0 },
+            }
+        );
+    }
+}
+
+//the weapon key (Tab) cycles weapons, number keys select one directly
+fn switch_weapon_system(
+    mut player: Query<&mut Weapon, With<PlayerShip>>,
+    keys: Res<Input<KeyCode>>,
+    settings: Res<Settings>,
+) {
+    for mut weapon in player.iter_mut() {
+        let selected = if keys.just_pressed(settings.keys.next_weapon) {
+            Some(weapon.kind.next())
+        } else if keys.just_pressed(KeyCode::Key1) {
+            Some(WeaponKind::Spread)
+        } else if keys.just_pressed(KeyCode::Key2) {
+            Some(WeaponKind::Rapid)
+        } else if keys.just_pressed(KeyCode::Key3) {
+            Some(WeaponKind::Laser)
+        } else if keys.just_pressed(KeyCode::Key4) {
+            Some(WeaponKind::Homing)
+        } else if keys.just_pressed(KeyCode::Key5) {
+            Some(WeaponKind::Bouncing)
+        } else {
+            None
+        };
+        if let Some(kind) = selected {
+            weapon.kind = kind;
+            weapon.frames_to_fire = 0;
+        }
+    }
+}
+
+fn move_bullet_system(
+    mut commands: Commands,
+    mut bullets: Query<(Entity, &mut Transform, &mut Bullet, &mut Visible)>,
+    enemies: Query<(&Transform, &Visible), (With<Enemy>, Without<Bullet>)>,
+    mut pool: ResMut<Pool<Bullet>>,
+    arena: Res<Arena>,
+    time: Res<TimeScale>,
+) {
+    let half_w = arena.width / 2.;
+    let half_h = arena.height / 2.;
+
+    for (entity, mut tr, mut bullet, mut visible) in bullets.iter_mut() {
+        //hidden bullets are waiting in the pool
+        if !visible.is_visible {
+            continue;
+        }
+        let pos = Vec2::new(tr.translation.x, tr.translation.y);
+        if bullet.homing {
+            //steer towards the nearest enemy, keeping the speed
+            let nearest = enemies
+                .iter()
+                .filter(|(_, visible)| visible.is_visible)
+                .map(|(e, _)| Vec2::new(e.translation.x, e.translation.y))
+                .min_by(|a, b| {
+                    //NaN positions compare as equal instead of panicking
+                    a.distance_squared(pos)
+                        .partial_cmp(&b.distance_squared(pos))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            if let Some(target) = nearest {
+                let speed = bullet.velocity.length();
+                let desired = (target - pos).normalize_or_zero() * speed;
+                let steered = bullet.velocity.lerp(desired, 0.1 * time.delta).normalize_or_zero();
+                //keep the old heading when the steering cancels it out
+                if steered != Vec2::ZERO {
+                    bullet.velocity = steered * speed;
+                }
+                tr.rotation = Quat::from_rotation_z(Vec2::Y.angle_between(bullet.velocity));
+            }
+        }
+        tr.translation.x += bullet.velocity.x * time.delta;
+        tr.translation.y += bullet.velocity.y * time.delta;
+
+        //reflect at the arena edge while bounces remain
+        if bullet.bounces > 0 {
+            let mut bounced = false;
+            if tr.translation.x < -half_w || tr.translation.x > half_w {
+                bullet.velocity.x = -bullet.velocity.x;
+                tr.translation.x = tr.translation.x.max(-half_w).min(half_w);
+                bounced = true;
+            }
+            if tr.translation.y < -half_h || tr.translation.y > half_h {
+                bullet.velocity.y = -bullet.velocity.y;
+                tr.translation.y = tr.translation.y.max(-half_h).min(half_h);
+                bounced = true;
+            }
+            if bounced {
+                bullet.bounces -= 1;
+                tr.rotation = Quat::from_rotation_z(Vec2::Y.angle_between(bullet.velocity));
+            }
+        }
+
+        //return it to the pool if outside of the arena
+        let tr = &tr.translation;
+        if tr.x < -half_w || tr.x > half_w || tr.y < -half_h || tr.y > half_h {
+            if !pool.release(entity, &mut visible) {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn console_command_system(
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    console_commands: Res<ConsoleCommands>,
+    mut options: ResMut<DevOptions>,
+) {
+    for event in events.iter() {
+        if event.command != "set fire_rate" {
+            continue;
+        }
+        match event.arg::<f32>(0) {
+            Some(rate) if rate > 0.0 => {
+                options.fire_rate = rate;
+                log.print(format!("fire_rate {}", rate));
+            }
+            _ => log.usage(&console_commands, event),
+        }
+    }
+}
+
+// Plugin
+pub struct BulletPlugin;
+
+impl Plugin for BulletPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_console_command("set fire_rate", "set fire_rate <multiplier>")
+            .add_system(console_command_system.system())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(timed(fire_bullet_system.system()))
+                    .with_system(timed(switch_weapon_system.system()))
+                    .with_system(timed(move_bullet_system.system())),
+            );
+    }
+
+    fn name(&self) -> &str {
+        "BulletSystem"
+    }
+}
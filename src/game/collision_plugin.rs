@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use crate::components::{PlayerShip, Bullet, Enemy, EnemyBullet, EnemyKilledEvent, PlayerHitEvent};
+use crate::components::{CollisionRadii, CollisionStats};
+use crate::components::AppState;
+use crate::pool::Pool;
+use crate::profiler_plugin::timed;
+
+//The enemy-death path, shared by everything that can destroy an enemy
+pub fn kill_enemy(
+    commands: &mut Commands,
+    killed_events: &mut EventWriter<EnemyKilledEvent>,
+    pool: &mut Pool<Enemy>,
+    entity: Entity,
+    transform: &Transform,
+    visible: &mut Visible,
+) {
+    //enemies not from the pool, like bosses, are despawned
+    if !pool.release(entity, visible) {
+        visible.is_visible = false;
+        commands.entity(entity).despawn();
+    }
+    killed_events.send(EnemyKilledEvent { entity, position: transform.translation });
+}
+
+fn bullet_enemy_collision_system(
+    mut commands: Commands,
+    mut bullets: Query<(Entity, &Transform, &mut Bullet, &mut Visible)>,
+    mut enemies: Query<(Entity, &Transform, &mut Enemy, &mut Visible), Without<Bullet>>,
+    mut bullet_pool: ResMut<Pool<Bullet>>,
+    mut enemy_pool: ResMut<Pool<Enemy>>,
+    mut killed_events: EventWriter<EnemyKilledEvent>,
+    radii: Res<CollisionRadii>,
+    mut stats: ResMut<CollisionStats>,
+) {
+    let mut checks = 0;
+    for (b_entity, b_tr, mut bullet, mut b_visible) in bullets.iter_mut() {
+        if !b_visible.is_visible {
+            continue;
+        }
+        for (e_entity, e_tr, mut enemy, mut e_visible) in enemies.iter_mut() {
+            //if already the enemy was killed or pierced by this bullet, not check.
+            //killed ones stay in the query until the end of the frame, so check the health too.
+            if !e_visible.is_visible
+                || enemy.health <= 0
+                || bullet.hit_enemies.contains(&e_entity)
+            {
+                continue;
+            }
+            //use circle collision to make this logic simple
+            checks += 1;
+            let diff = b_tr.translation.distance(e_tr.translation);
+            if diff < radii.bullet_enemy {
+                //hit! damage the enemy and remove it when its health runs out.
+                enemy.health -= bullet.damage;
+                if enemy.health <= 0 {
+                    kill_enemy(
+                        &mut commands,
+                        &mut killed_events,
+                        &mut enemy_pool,
+                        e_entity,
+                        e_tr,
+                        &mut e_visible,
+                    );
+                }
+                if bullet.piercing {
+                    bullet.hit_enemies.push(e_entity);
+                } else {
+                    if !bullet_pool.release(b_entity, &mut b_visible) {
+                        commands.entity(b_entity).despawn();
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    stats.checks += checks;
+}
+
+fn player_enemy_collision_system(
+    queries: QuerySet<(
+        Query<&Transform, With<PlayerShip>>,
+        Query<(&Transform, &Visible), With<Enemy>>
+    )>,
+    mut hit_events: EventWriter<PlayerHitEvent>,
+    radii: Res<CollisionRadii>,
+    mut stats: ResMut<CollisionStats>,
+) {
+    let players = queries.q0();
+    let enemies = queries.q1();
+    if let Some(player) = players.iter().next() {
+        for (enemy, visible) in enemies.iter() {
+            if !visible.is_visible {
+                continue;
+            }
+            stats.checks += 1;
+            let diff = player.translation.distance(enemy.translation);
+            if diff < radii.player_enemy {
+                //Hit! the player ship decides what happens
+                hit_events.send(PlayerHitEvent { position: player.translation });
+                break;
+            }
+        }
+    }
+}
+
+fn enemy_bullet_player_collision_system(
+    mut commands: Commands,
+    players: Query<&Transform, With<PlayerShip>>,
+    mut bullets: Query<(Entity, &Transform, &mut Visible), With<EnemyBullet>>,
+    mut pool: ResMut<Pool<EnemyBullet>>,
+    mut hit_events: EventWriter<PlayerHitEvent>,
+    radii: Res<CollisionRadii>,
+    mut stats: ResMut<CollisionStats>,
+) {
+    if let Some(player) = players.iter().next() {
+        for (entity, bullet, mut visible) in bullets.iter_mut() {
+            if !visible.is_visible {
+                continue;
+            }
+            stats.checks += 1;
+            let diff = player.translation.distance(bullet.translation);
+            if diff < radii.enemy_bullet_player {
+                if !pool.release(entity, &mut visible) {
+                    commands.entity(entity).despawn();
+                }
+                hit_events.send(PlayerHitEvent { position: player.translation });
+            }
+        }
+    }
+}
+
+
+
+
+// Plugin
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<CollisionRadii>()
+            .init_resource::<CollisionStats>()
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(timed(bullet_enemy_collision_system.system()))
+                    .with_system(timed(player_enemy_collision_system.system()))
+                    .with_system(timed(enemy_bullet_player_collision_system.system())),
+            );
+    }
+
+    fn name(&self) -> &str {
+        "Collision"
+    }
+}
\ No newline at end of file
@@ -0,0 +1,785 @@
+use bevy::prelude::*;
+
+//Scenes of the game, used with State
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AppState {
+    //waiting for GameAssets
+    Loading,
+    //some assets are missing or broken
+    LoadError,
+    //title screen and main menu
+    Title,
+    //pushed on top of Title
+    HighScores,
+    InGame,
+    //pushed on top of InGame, so gameplay systems stop running
+    Paused,
+    //final score and initials entry
+    GameOver,
+    //pushed on top of InGame for one frame to restore a saved run
+    LoadingRun,
+    //pushed on top of the screen it was opened from
+    Settings,
+    //developer console, pushed on top of InGame
+    Console,
+}
+
+pub struct PlayerShip {
+    pub target_x: f32,
+    pub target_y: f32,
+    //true while move_player_system is moving the ship
+    pub moving: bool,
+    pub lives: i32,
+    pub bombs: i32,
+    //timed effects from power-ups, active while > 0
+    pub shield_frames: i32,
+    pub upgrade_frames: i32,
+}
+impl Default for PlayerShip {
+    fn default() -> Self {
+        PlayerShip {
+            target_x: 0.,
+            target_y: 0.,
+            moving: false,
+            lives: 3,
+            bombs: 2,
+            shield_frames: 0,
+            upgrade_frames: 0,
+        }
+    }
+}
+
+pub struct Bullet {
+    //the weapon that fired it
+    pub kind: WeaponKind,
+    pub velocity: Vec2,
+    pub damage: i32,
+    //piercing bullets are not removed when they hit, remember the enemies instead
+    pub piercing: bool,
+    pub hit_enemies: Vec<Entity>,
+    pub homing: bool,
+    //how many times the bullet can reflect at the window edge
+    pub bounces: i32,
+}
+
+//Weapon types the player can switch between
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WeaponKind {
+    Spread,
+    Rapid,
+    Laser,
+    Homing,
+    Bouncing,
+}
+
+//Parameters for each weapon type
+pub struct WeaponSpec {
+    pub frames_per_shot: i32,
+    pub speed: f32,
+    pub damage: i32,
+    pub texture: &'static str,
+    pub size: Vec2,
+    pub color: Color,
+}
+
+impl WeaponKind {
+    pub fn spec(&self) -> WeaponSpec {
+        match self {
+            WeaponKind::Spread => WeaponSpec {
+                frames_per_shot: 12,
+                speed: 8.0,
+                damage: HIT,
+                texture: "elipse.png",
+                size: Vec2::new(10.0, 20.0),
+                color: Color::WHITE,
+            },
+            WeaponKind::Rapid => WeaponSpec {
+                frames_per_shot: 3,
+                speed: 12.0,
+                damage: HIT,
+                texture: "elipse.png",
+                size: Vec2::new(6.0, 14.0),
+                color: Color::YELLOW,
+            },
+            WeaponKind::Laser => WeaponSpec {
+                frames_per_shot: 20,
+                speed: 16.0,
+                damage: 2 * HIT,
+                texture: "elipse.png",
+                size: Vec2::new(4.0, 40.0),
+                color: Color::CYAN,
+            },
+            WeaponKind::Homing => WeaponSpec {
+                frames_per_shot: 25,
+                speed: 5.0,
+                damage: 3 * HIT,
+                texture: "triangle.png",
+                size: Vec2::new(12.0, 12.0),
+                color: Color::ORANGE,
+            },
+            WeaponKind::Bouncing => WeaponSpec {
+                frames_per_shot: 10,
+                speed: 7.0,
+                damage: HIT,
+                texture: "circle.png",
+                size: Vec2::new(10.0, 10.0),
+                color: Color::GREEN,
+            },
+        }
+    }
+
+    pub fn next(&self) -> WeaponKind {
+        match self {
+            WeaponKind::Spread => WeaponKind::Rapid,
+            WeaponKind::Rapid => WeaponKind::Laser,
+            WeaponKind::Laser => WeaponKind::Homing,
+            WeaponKind::Homing => WeaponKind::Bouncing,
+            WeaponKind::Bouncing => WeaponKind::Spread,
+        }
+    }
+}
+
+//Weapon attached to the player ship, replaced at runtime
+pub struct Weapon {
+    pub kind: WeaponKind,
+    pub frames_to_fire: i32,
+}
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            kind: WeaponKind::Spread,
+            frames_to_fire: 0,
+        }
+    }
+}
+
+//Enemies Management used as Resource
+pub struct GlobalEnemyState {
+    pub frames_to_next_enemy: i32,
+    pub spawn_interval: i32,
+    //index into Waves, keeps counting after the last definition
+    pub wave: usize,
+    pub spawned_in_wave: i32,
+    pub boss_spawned: bool,
+}
+impl Default for GlobalEnemyState {
+    fn default() -> Self {
+        GlobalEnemyState {
+            frames_to_next_enemy: 20,
+            spawn_interval: 20,
+            wave: 0,
+            spawned_in_wave: 0,
+            boss_spawned: false,
+        }
+    }
+}
+
+//One wave of enemies
+pub struct WaveDefinition {
+    pub enemy_count: i32,
+    pub spawn_interval: i32,
+    //spawned after all the other enemies of the wave are gone
+    pub boss: bool,
+}
+
+//The wave sequence used as Resource, repeated when it ends
+pub struct Waves(pub Vec<WaveDefinition>);
+impl Default for Waves {
+    fn default() -> Self {
+        Waves(vec![
+            WaveDefinition { enemy_count: 10, spawn_interval: 90, boss: false },
+            WaveDefinition { enemy_count: 15, spawn_interval: 60, boss: false },
+            WaveDefinition { enemy_count: 20, spawn_interval: 45, boss: false },
+            WaveDefinition { enemy_count: 10, spawn_interval: 60, boss: true },
+        ])
+    }
+}
+impl Waves {
+    pub fn get(&self, wave: usize) -> &WaveDefinition {
+        &self.0[wave % self.0.len()]
+    }
+}
+
+//Health and damage are counted in thirds of a basic shot, so the difficulty can scale the health
+//of an enemy a basic shot destroys: 2 on Easy, 3 on Normal, 4 on Hard
+pub const HIT: i32 = 3;
+
+//One Enemy
+pub struct Enemy {
+    //when this==0, enemy can move and has collision.
+    pub frames_to_appear: i32,
+    pub health: i32,
+    //per frame, changed by the steering in move_enemy_system
+    pub velocity: Vec2,
+}
+impl Default for Enemy {
+    fn default() -> Self {
+        Enemy {
+            frames_to_appear: 10,
+            health: HIT,
+            velocity: Vec2::ZERO,
+        }
+    }
+}
+
+//How an enemy archetype steers, each behavior is a unit direction scaled by its weight
+#[derive(Clone, Copy, Debug)]
+pub struct SteeringWeights {
+    //away from neighbours that are too close
+    pub separation: f32,
+    //the same heading as the neighbours
+    pub alignment: f32,
+    //towards the center of the neighbours
+    pub cohesion: f32,
+    //towards the player
+    pub seek: f32,
+    //away from the player's bullets nearby
+    pub flee: f32,
+    //multiplies the base enemy speed
+    pub speed: f32,
+}
+
+//Steering weights for each enemy archetype, used as Resource.
+//The archetype is the fire pattern of the enemy, plain ones don't shoot.
+pub struct Steering {
+    pub plain: SteeringWeights,
+    pub aimed: SteeringWeights,
+    pub radial: SteeringWeights,
+    pub spiral: SteeringWeights,
+}
+impl Default for Steering {
+    fn default() -> Self {
+        Steering {
+            //chasers, the bulk of a swarm
+            plain: SteeringWeights {
+                separation: 1.5,
+                alignment: 0.5,
+                cohesion: 0.3,
+                seek: 1.0,
+                flee: 0.8,
+                speed: 1.0,
+            },
+            //keep out of the bullets to go on shooting
+            aimed: SteeringWeights {
+                separation: 1.5,
+                alignment: 0.3,
+                cohesion: 0.2,
+                seek: 0.6,
+                flee: 1.5,
+                speed: 0.9,
+            },
+            //flock tightly, so their bursts come from a crowd
+            radial: SteeringWeights {
+                separation: 1.0,
+                alignment: 1.0,
+                cohesion: 1.0,
+                seek: 0.8,
+                flee: 0.5,
+                speed: 1.0,
+            },
+            //drift along with the flock
+            spiral: SteeringWeights {
+                separation: 1.2,
+                alignment: 1.2,
+                cohesion: 0.6,
+                seek: 0.4,
+                flee: 0.5,
+                speed: 0.8,
+            },
+        }
+    }
+}
+impl Steering {
+    pub fn weights(&self, shooter: &Shooter) -> &SteeringWeights {
+        if !shooter.enabled {
+            return &self.plain;
+        }
+        match shooter.pattern {
+            FirePattern::Aimed => &self.aimed,
+            FirePattern::RadialBurst(_) => &self.radial,
+            FirePattern::Spiral => &self.spiral,
+        }
+    }
+}
+
+//Boss core, its health is kept in Enemy like other enemies
+pub struct Boss {
+    pub max_health: i32,
+    //0, 1, 2... changes at health thresholds
+    pub phase: usize,
+    pub frames: i32,
+}
+
+//A hittable part attached to a boss, follows the boss with an offset
+pub struct BossPart {
+    pub boss: Entity,
+    pub offset: Vec2,
+}
+
+//Marker for the health bar shown while a boss is alive
+pub struct BossHealthBar;
+
+//Sent when an enemy is destroyed, other plugins react to it (drops, effects...)
+pub struct EnemyKilledEvent {
+    pub entity: Entity,
+    pub position: Vec3,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerUpKind {
+    WeaponUpgrade,
+    Shield,
+    ExtraLife,
+    Bomb,
+}
+
+//An item dropped by an enemy
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub velocity: Vec2,
+    pub frames_to_expire: i32,
+}
+
+//Probability of each power-up dropping from a killed enemy, used as Resource
+pub struct DropTable {
+    pub weapon_upgrade: f32,
+    pub shield: f32,
+    pub extra_life: f32,
+    pub bomb: f32,
+    pub frames_to_expire: i32,
+}
+impl Default for DropTable {
+    fn default() -> Self {
+        DropTable {
+            weapon_upgrade: 0.08,
+            shield: 0.05,
+            extra_life: 0.01,
+            bomb: 0.03,
+            frames_to_expire: 600,
+        }
+    }
+}
+impl DropTable {
+    //pick a power-up using a random value in [0..1)
+    pub fn choose(&self, value: f32) -> Option<PowerUpKind> {
+        let table = [
+            (PowerUpKind::WeaponUpgrade, self.weapon_upgrade),
+            (PowerUpKind::Shield, self.shield),
+            (PowerUpKind::ExtraLife, self.extra_life),
+            (PowerUpKind::Bomb, self.bomb),
+        ];
+        let mut total = 0.;
+        for (kind, probability) in table.iter() {
+            total += probability;
+            if value < total {
+                return Some(*kind);
+            }
+        }
+        None
+    }
+}
+
+//Expanding ring created by a bomb, damages every enemy it reaches once
+pub struct Shockwave {
+    pub radius: f32,
+    pub max_radius: f32,
+    pub speed: f32,
+    pub damage: i32,
+    pub hit_enemies: Vec<Entity>,
+}
+impl Default for Shockwave {
+    fn default() -> Self {
+        Shockwave {
+            radius: 0.,
+            max_radius: 200.,
+            speed: 6.,
+            damage: 10 * HIT,
+            hit_enemies: Vec::new(),
+        }
+    }
+}
+
+//Distances at which things collide, used as Resource.
+//Can be tuned while the game runs with the debug overlay.
+pub struct CollisionRadii {
+    pub bullet_enemy: f32,
+    pub player_enemy: f32,
+    pub enemy_bullet_player: f32,
+}
+impl Default for CollisionRadii {
+    fn default() -> Self {
+        CollisionRadii {
+            bullet_enemy: 20.0,
+            player_enemy: 20.0,
+            enemy_bullet_player: 12.0,
+        }
+    }
+}
+
+//Collision pairs tested since the start, used as Resource
+#[derive(Default)]
+pub struct CollisionStats {
+    pub checks: u64,
+}
+
+//Sent when something hits the player ship
+pub struct PlayerHitEvent {
+    pub position: Vec3,
+}
+
+//Projectile fired by an enemy, only collides with the player
+pub struct EnemyBullet {
+    pub velocity: Vec2,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FirePattern {
+    //one bullet towards the player
+    Aimed,
+    //bullets in every direction at once
+    RadialBurst(u32),
+    //one bullet per shot, rotating each time
+    Spiral,
+}
+
+//Attached to enemies that can shoot
+pub struct Shooter {
+    pub enabled: bool,
+    pub pattern: FirePattern,
+    pub interval: i32,
+    pub frames_to_fire: i32,
+    pub bullet_speed: f32,
+    //current direction for Spiral
+    pub angle: f32,
+}
+impl Shooter {
+    pub fn new(pattern: FirePattern) -> Self {
+        let interval = match pattern {
+            FirePattern::Aimed => 120,
+            FirePattern::RadialBurst(_) => 180,
+            FirePattern::Spiral => 8,
+        };
+        Shooter {
+            enabled: true,
+            pattern,
+            interval,
+            frames_to_fire: interval,
+            bullet_speed: 2.0,
+            angle: 0.,
+        }
+    }
+
+    //for enemies that never shoot
+    pub fn disabled() -> Self {
+        Shooter {
+            enabled: false,
+            ..Shooter::new(FirePattern::Aimed)
+        }
+    }
+}
+
+//One sprite particle, owned by the particle pool and hidden while not active
+pub struct Particle {
+    pub velocity: Vec2,
+    //velocity is multiplied by this every frame
+    pub drag: f32,
+    pub age: i32,
+    pub lifetime: i32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+}
+impl Default for Particle {
+    fn default() -> Self {
+        Particle {
+            velocity: Vec2::ZERO,
+            drag: 1.0,
+            age: 0,
+            lifetime: 0,
+            start_color: Color::WHITE,
+            end_color: Color::WHITE,
+            start_size: 0.,
+            end_size: 0.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sound {
+    Shot,
+    Explosion,
+    PlayerHit,
+    Pickup,
+    TitleMusic,
+    GameMusic,
+}
+
+//Request to play a sound effect
+pub struct SoundEvent(pub Sound);
+
+//Marker for entities only shown while loading
+pub struct LoadingScreen;
+
+//Marker for the HUD text
+pub struct HudText;
+
+//Marker for the pause overlay
+pub struct PauseMenu;
+
+//Marker for the settings screen
+pub struct SettingsMenu;
+
+//Marker for the title screen
+pub struct TitleScreen;
+
+//Marker for the developer console
+pub struct ConsoleScreen;
+
+//Marker for the text of the developer console
+pub struct ConsoleText;
+
+//Options changed with the developer console, used as Resource
+pub struct DevOptions {
+    //the ship can't be hit
+    pub god: bool,
+    //multiplies the shots per second of every weapon
+    pub fire_rate: f32,
+    //seed for the next runs instead of a random one, to replay a run
+    pub seed: Option<u64>,
+}
+impl Default for DevOptions {
+    fn default() -> Self {
+        DevOptions {
+            god: false,
+            fire_rate: 1.0,
+            seed: None,
+        }
+    }
+}
+
+//Size of the play area centered at the origin, used as Resource.
+//Follows the primary window unless GameConfig gives a fixed size.
+pub struct Arena {
+    pub width: f32,
+    pub height: f32,
+}
+impl Default for Arena {
+    fn default() -> Self {
+        Arena {
+            width: 480.0,
+            height: 320.0,
+        }
+    }
+}
+impl Arena {
+    //smaller sizes are raised to MIN_ARENA_SIZE, enemies need room to appear away from the ship
+    pub fn new(width: f32, height: f32) -> Self {
+        Arena {
+            width: width.max(MIN_ARENA_SIZE),
+            height: height.max(MIN_ARENA_SIZE),
+        }
+    }
+}
+
+//smallest width and height of the play area
+pub const MIN_ARENA_SIZE: f32 = 100.0;
+
+//Difficulty presets, chosen in the settings
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DifficultyLevel {
+    Easy,
+    Normal,
+    Hard,
+}
+
+//Multipliers for each difficulty level, all 1.0 on Normal
+pub struct DifficultySpec {
+    pub enemy_speed: f32,
+    pub enemy_health: f32,
+    //multiplies the frames between enemy spawns
+    pub spawn_interval: f32,
+    //multiplies the shots per second of enemies
+    pub fire_rate: f32,
+}
+
+impl DifficultyLevel {
+    pub fn spec(&self) -> DifficultySpec {
+        match self {
+            DifficultyLevel::Easy => DifficultySpec {
+                enemy_speed: 0.75,
+                enemy_health: 0.67,
+                spawn_interval: 1.4,
+                fire_rate: 0.7,
+            },
+            DifficultyLevel::Normal => DifficultySpec {
+                enemy_speed: 1.0,
+                enemy_health: 1.0,
+                spawn_interval: 1.0,
+                fire_rate: 1.0,
+            },
+            DifficultyLevel::Hard => DifficultySpec {
+                enemy_speed: 1.3,
+                enemy_health: 1.34,
+                spawn_interval: 0.7,
+                fire_rate: 1.4,
+            },
+        }
+    }
+
+    pub fn next(&self) -> DifficultyLevel {
+        match self {
+            DifficultyLevel::Easy => DifficultyLevel::Normal,
+            DifficultyLevel::Normal => DifficultyLevel::Hard,
+            DifficultyLevel::Hard => DifficultyLevel::Easy,
+        }
+    }
+
+    //as written by {:?}, case is ignored
+    pub fn parse(name: &str) -> Option<DifficultyLevel> {
+        match name.to_ascii_lowercase().as_str() {
+            "easy" => Some(DifficultyLevel::Easy),
+            "normal" => Some(DifficultyLevel::Normal),
+            "hard" => Some(DifficultyLevel::Hard),
+            _ => None,
+        }
+    }
+}
+
+//How hard the current run is, used as Resource
+pub struct Difficulty {
+    pub level: DifficultyLevel,
+    //spawn pressure follows how the player is doing
+    pub adaptive: bool,
+    //multiplies the spawn rate on top of the level, stays 1.0 unless adaptive
+    pub pressure: f32,
+}
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::new(DifficultyLevel::Normal, false)
+    }
+}
+impl Difficulty {
+    pub fn new(level: DifficultyLevel, adaptive: bool) -> Self {
+        Difficulty {
+            level,
+            adaptive,
+            pressure: 1.0,
+        }
+    }
+
+    //frames between spawns for the interval of a wave
+    pub fn spawn_interval(&self, frames: i32) -> i32 {
+        let frames = frames as f32 * self.level.spec().spawn_interval / self.pressure;
+        (frames.round() as i32).max(1)
+    }
+
+    pub fn enemy_health(&self, health: i32) -> i32 {
+        ((health as f32 * self.level.spec().enemy_health).round() as i32).max(1)
+    }
+
+    //frames between shots for the interval of a Shooter
+    pub fn fire_interval(&self, frames: i32) -> i32 {
+        ((frames as f32 / self.level.spec().fire_rate).round() as i32).max(1)
+    }
+}
+
+//Speed of the gameplay, used as Resource.
+//Movement is multiplied by delta. Frame counters go down by ticks, the whole frames of game time
+//passed in this frame, so at a scale of 0.25 they count once every 4 frames.
+pub struct TimeScale {
+    //set with the timescale console command
+    pub scale: f32,
+    //real frames left of the slow motion after the ship is hit
+    pub slow_motion_frames: i32,
+    //frame-step mode, the game only advances when a step is requested
+    pub stepping: bool,
+    pub delta: f32,
+    pub ticks: i32,
+    //game time not counted in ticks yet, in [0..1)
+    pub fraction: f32,
+}
+impl Default for TimeScale {
+    fn default() -> Self {
+        TimeScale {
+            scale: 1.0,
+            slow_motion_frames: 0,
+            stepping: false,
+            delta: 1.0,
+            ticks: 1,
+            fraction: 0.0,
+        }
+    }
+}
+
+//Marker for the high score screen
+pub struct HighScoreScreen;
+
+//Marker for the game over screen
+pub struct GameOverScreen;
+
+//Marker for the text of the game over screen
+pub struct GameOverText;
+
+//Score of the current run, used as Resource.
+//Kept after InGame is exited so the game over screen can show it.
+#[derive(Default)]
+pub struct Score {
+    pub points: u32,
+    //highest wave reached, counted from 1
+    pub wave: u32,
+    pub seed: u64,
+}
+
+//Random numbers for gameplay, used as Resource.
+//Seeded for each run and saved with it, so a resumed run continues the same sequence.
+#[derive(Default)]
+pub struct GameRng {
+    pub state: u64,
+}
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng { state: seed }
+    }
+
+    //splitmix64
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    //in [0..1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+//Marker for the text shown during the demo
+pub struct DemoText;
+
+//The demo played when the title screen is left idle, used as Resource.
+//While active, the ship is flown by demo_pilot_system instead of the mouse.
+#[derive(Default)]
+pub struct AttractMode {
+    pub active: bool,
+    pub frames: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_change_the_health_of_regular_enemies() {
+        let health = |level| Difficulty::new(level, false).enemy_health(Enemy::default().health);
+        let easy = health(DifficultyLevel::Easy);
+        let normal = health(DifficultyLevel::Normal);
+        let hard = health(DifficultyLevel::Hard);
+        assert!(easy < normal && normal < hard, "{} {} {}", easy, normal, hard);
+        //a basic shot still destroys a regular enemy on Normal
+        assert!(normal <= WeaponKind::Spread.spec().damage);
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use bevy::app::Events;
+use bevy::prelude::*;
+
+use crate::components::{AppState, AttractMode, ConsoleScreen, ConsoleText};
+use crate::loading_plugin::GameAssets;
+use crate::menu_plugin::{take_key, MenuMaterials, MENU_NAVIGATION};
+
+// Plugin
+//The developer console, opened in game with the ` key.
+//Console is pushed on top of InGame like Paused, so the game waits while a command is typed.
+//Plugins register their commands with add_console_command and handle ConsoleCommandEvent.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        //the console is drawn like the menus, also when MenuPlugin is left out
+        app.init_resource::<ConsoleInput>()
+            .init_resource::<MenuMaterials>()
+            .add_console_command("help", "help")
+            .add_console_command("clear", "clear")
+            .add_system(builtin_command_system.system())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame).with_system(open_console_system.system()),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Console).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::Console)
+                    .with_system(console_input_system.system().before(MENU_NAVIGATION))
+                    .with_system(console_text_system.system()),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Console).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "ConsolePlugin"
+    }
+}
+
+//lines of output kept on screen
+const MAX_LOG_LINES: usize = 12;
+
+//A command the console knows, names can be several words like "spawn enemy"
+pub struct ConsoleCommandInfo {
+    pub name: &'static str,
+    pub usage: &'static str,
+}
+
+//Every registered command, used as Resource
+#[derive(Default)]
+pub struct ConsoleCommands(pub Vec<ConsoleCommandInfo>);
+
+impl ConsoleCommands {
+    //the command with the longest name the words start with, and the number of words it takes
+    fn find(&self, words: &[&str]) -> Option<(&ConsoleCommandInfo, usize)> {
+        self.0
+            .iter()
+            .map(|info| (info, info.name.split_whitespace().collect::<Vec<_>>()))
+            .filter(|(_, name)| words.len() >= name.len() && words[..name.len()] == name[..])
+            .max_by_key(|(_, name)| name.len())
+            .map(|(info, name)| (info, name.len()))
+    }
+
+    pub fn usage(&self, name: &str) -> &'static str {
+        self.0
+            .iter()
+            .find(|info| info.name == name)
+            .map(|info| info.usage)
+            .unwrap_or("")
+    }
+}
+
+//Sent when a registered command is entered, the plugin that registered it handles it
+pub struct ConsoleCommandEvent {
+    pub command: &'static str,
+    pub args: Vec<String>,
+}
+
+impl ConsoleCommandEvent {
+    //None if the argument is missing or can't be parsed
+    pub fn arg<T: FromStr>(&self, index: usize) -> Option<T> {
+        self.args.get(index).and_then(|arg| arg.parse().ok())
+    }
+}
+
+//Output of the console, used as Resource. Command handlers print their result here.
+#[derive(Default)]
+pub struct ConsoleLog {
+    lines: VecDeque<String>,
+}
+
+impl ConsoleLog {
+    pub fn print<S: Into<String>>(&mut self, line: S) {
+        let line = line.into();
+        info!("console: {}", line);
+        self.lines.push_back(line);
+        while self.lines.len() > MAX_LOG_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    //the usage of the command, when its arguments are wrong
+    pub fn usage(&mut self, commands: &ConsoleCommands, event: &ConsoleCommandEvent) {
+        self.print(format!("usage: {}", commands.usage(event.command)));
+    }
+}
+
+pub trait ConsoleAppExt {
+    fn add_console_command(&mut self, name: &'static str, usage: &'static str) -> &mut Self;
+}
+
+impl ConsoleAppExt for AppBuilder {
+    //plugins can register commands before or after ConsolePlugin is added
+    fn add_console_command(&mut self, name: &'static str, usage: &'static str) -> &mut Self {
+        if !self.world_mut().contains_resource::<Events<ConsoleCommandEvent>>() {
+            self.add_event::<ConsoleCommandEvent>();
+        }
+        self.init_resource::<ConsoleLog>();
+        self.world_mut()
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .0
+            .push(ConsoleCommandInfo { name, usage });
+        self
+    }
+}
+
+//The line being typed, used as Resource
+#[derive(Default)]
+struct ConsoleInput {
+    line: String,
+    history: Vec<String>,
+    //position in history while browsing it with Up/Down
+    history_index: usize,
+    //characters typed in game before the console was opened are dropped
+    fresh: bool,
+}
+
+fn open_console_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut state: ResMut<State<AppState>>,
+    attract: Res<AttractMode>,
+) {
+    if attract.active {
+        return;
+    }
+    //pausing or game over may be queued in the same frame, then the console stays closed
+    if take_key(&mut keys, KeyCode::Grave) {
+        let _ = state.push(AppState::Console);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    materials: Res<MenuMaterials>,
+    mut input: ResMut<ConsoleInput>,
+) {
+    input.line.clear();
+    input.history_index = input.history.len();
+    input.fresh = true;
+    commands
+        .spawn()
+        .insert_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Px(180.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    ..Default::default()
+                },
+                padding: Rect::all(Val::Px(6.0)),
+                ..Default::default()
+            },
+            material: materials.background.clone(),
+            ..Default::default()
+        })
+        .insert(ConsoleScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 12.0,
+                            color: Color::WHITE,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(ConsoleText);
+        });
+}
+
+fn cleanup(
+    mut commands: Commands,
+    screens: Query<Entity, With<ConsoleScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn console_input_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    mut input: ResMut<ConsoleInput>,
+    mut log: ResMut<ConsoleLog>,
+    commands: Res<ConsoleCommands>,
+    mut events: EventWriter<ConsoleCommandEvent>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if input.fresh {
+        for _ in chars.iter() {}
+        input.fresh = false;
+        return;
+    }
+    if take_key(&mut keys, KeyCode::Escape) | take_key(&mut keys, KeyCode::Grave) {
+        state.pop().unwrap();
+        return;
+    }
+    for c in chars.iter().map(|e| e.char) {
+        if c != '`' && !c.is_control() {
+            input.line.push(c);
+        }
+    }
+    if take_key(&mut keys, KeyCode::Back) {
+        input.line.pop();
+    }
+    //recall earlier commands
+    if take_key(&mut keys, KeyCode::Up) && input.history_index > 0 {
+        input.history_index -= 1;
+        input.line = input.history[input.history_index].clone();
+    }
+    if take_key(&mut keys, KeyCode::Down) && input.history_index < input.history.len() {
+        input.history_index += 1;
+        input.line = input.history.get(input.history_index).cloned().unwrap_or_default();
+    }
+    if !take_key(&mut keys, KeyCode::Return) {
+        return;
+    }
+    let line = std::mem::take(&mut input.line);
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+        return;
+    }
+    log.print(format!("> {}", line));
+    input.history.push(line.clone());
+    input.history_index = input.history.len();
+    match commands.find(&words) {
+        Some((info, length)) => events.send(ConsoleCommandEvent {
+            command: info.name,
+            args: words[length..].iter().map(|word| word.to_string()).collect(),
+        }),
+        None => log.print(format!("unknown command: {}, try help", words[0])),
+    }
+}
+
+fn console_text_system(
+    input: Res<ConsoleInput>,
+    log: Res<ConsoleLog>,
+    mut texts: Query<&mut Text, With<ConsoleText>>,
+) {
+    let mut message = String::new();
+    for line in log.lines.iter() {
+        message.push_str(line);
+        message.push('\n');
+    }
+    message.push_str(&format!("> {}_", input.line));
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
+
+fn builtin_command_system(
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    commands: Res<ConsoleCommands>,
+) {
+    for event in events.iter() {
+        match event.command {
+            "help" => {
+                let mut usages = commands.0.iter().map(|info| info.usage).collect::<Vec<_>>();
+                usages.sort_unstable();
+                for usage in usages {
+                    log.print(usage);
+                }
+            }
+            "clear" => log.lines.clear(),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::components::{
+    Boss, Bullet, CollisionRadii, Enemy, EnemyBullet, Particle, PlayerShip, PowerUp, Shockwave,
+    Shooter, Weapon,
+};
+use crate::loading_plugin::GameAssets;
+
+// Plugin
+//F3 toggles an overlay drawing colliders, enemy velocities and the ship's target,
+//with FPS and entity counts. While it's shown, clicking an entity inspects it,
+//and [ ] change the bullet-enemy radius, - = the player-enemy radius.
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin::default())
+            .init_resource::<DebugOverlay>()
+            .init_resource::<DebugLines>()
+            //tuned here even without CollisionPlugin
+            .init_resource::<CollisionRadii>()
+            .add_startup_system(setup.system())
+            .add_system(toggle_debug_system.system())
+            .add_system(tune_radius_system.system())
+            .add_system(collect_lines_system.system().label(COLLECT_LINES))
+            .add_system(draw_lines_system.system().after(COLLECT_LINES))
+            .add_system(stats_text_system.system())
+            .add_system(pick_entity_system.system())
+            .add_system(inspector_text_system.system());
+    }
+    fn name(&self) -> &str {
+        "DebugPlugin"
+    }
+}
+
+const COLLECT_LINES: &str = "debug_collect_lines";
+
+//segments of a circle outline
+const CIRCLE_SEGMENTS: usize = 16;
+//a velocity is drawn this many frames long to be visible
+const VELOCITY_SCALE: f32 = 20.0;
+//draw over everything else
+const DEBUG_Z: f32 = 50.0;
+
+//State of the overlay, used as Resource
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    //entity shown in the inspector
+    pub selected: Option<Entity>,
+}
+
+//Segments to draw this frame and the sprites drawing them, used as Resource
+#[derive(Default)]
+pub struct DebugLines {
+    lines: Vec<(Vec2, Vec2, Handle<ColorMaterial>)>,
+    sprites: Vec<Entity>,
+    //positions in the last frame, to show how far enemies moved
+    last_positions: HashMap<Entity, Vec2>,
+}
+
+impl DebugLines {
+    fn line(&mut self, from: Vec2, to: Vec2, material: &Handle<ColorMaterial>) {
+        self.lines.push((from, to, material.clone()));
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32, material: &Handle<ColorMaterial>) {
+        let step = std::f32::consts::PI * 2. / CIRCLE_SEGMENTS as f32;
+        for i in 0..CIRCLE_SEGMENTS {
+            let a = step * i as f32;
+            let b = step * (i + 1) as f32;
+            self.line(
+                center + Vec2::new(a.cos(), a.sin()) * radius,
+                center + Vec2::new(b.cos(), b.sin()) * radius,
+                material,
+            );
+        }
+    }
+}
+
+//Materials of the overlay, used as Resource
+struct DebugMaterials {
+    collider: Handle<ColorMaterial>,
+    player_collider: Handle<ColorMaterial>,
+    velocity: Handle<ColorMaterial>,
+    target: Handle<ColorMaterial>,
+    selected: Handle<ColorMaterial>,
+}
+
+//Marker for the FPS and counts text
+struct DebugStatsText;
+
+//Marker for the inspector text
+struct DebugInspectorText;
+
+fn setup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    assets: Res<GameAssets>,
+) {
+    commands.insert_resource(DebugMaterials {
+        collider: materials.add(Color::GREEN.into()),
+        player_collider: materials.add(Color::CYAN.into()),
+        velocity: materials.add(Color::YELLOW.into()),
+        target: materials.add(Color::FUCHSIA.into()),
+        selected: materials.add(Color::RED.into()),
+    });
+    let style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 11.0,
+        color: Color::GREEN,
+    };
+    commands
+        .spawn()
+        .insert_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(22.0),
+                    right: Val::Px(6.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section("", style.clone(), Default::default()),
+            ..Default::default()
+        })
+        .insert(DebugStatsText);
+    commands
+        .spawn()
+        .insert_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(22.0),
+                    left: Val::Px(6.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section("", style, Default::default()),
+            ..Default::default()
+        })
+        .insert(DebugInspectorText);
+}
+
+fn toggle_debug_system(
+    keys: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        overlay.enabled = !overlay.enabled;
+        overlay.selected = None;
+    }
+}
+
+fn tune_radius_system(
+    keys: Res<Input<KeyCode>>,
+    overlay: Res<DebugOverlay>,
+    mut radii: ResMut<CollisionRadii>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    let changes: [(KeyCode, f32, f32); 4] = [
+        (KeyCode::LBracket, -1.0, 0.0),
+        (KeyCode::RBracket, 1.0, 0.0),
+        (KeyCode::Minus, 0.0, -1.0),
+        (KeyCode::Equals, 0.0, 1.0),
+    ];
+    for (key, bullet_enemy, player_enemy) in changes.iter() {
+        if keys.just_pressed(*key) {
+            radii.bullet_enemy = (radii.bullet_enemy + bullet_enemy).max(1.0);
+            radii.player_enemy = (radii.player_enemy + player_enemy).max(1.0);
+            info!(
+                "collision radius: bullet-enemy {}, player-enemy {}",
+                radii.bullet_enemy, radii.player_enemy
+            );
+        }
+    }
+}
+
+fn position(tr: &Transform) -> Vec2 {
+    Vec2::new(tr.translation.x, tr.translation.y)
+}
+
+fn collect_lines_system(
+    overlay: Res<DebugOverlay>,
+    mut lines: ResMut<DebugLines>,
+    materials: Res<DebugMaterials>,
+    radii: Res<CollisionRadii>,
+    player: Query<(&Transform, &PlayerShip)>,
+    enemies: Query<(Entity, &Transform, &Visible), With<Enemy>>,
+    bullets: Query<(&Transform, &Visible), With<Bullet>>,
+    enemy_bullets: Query<(&Transform, &Visible), With<EnemyBullet>>,
+    all: Query<&Transform>,
+) {
+    lines.lines.clear();
+    if !overlay.enabled {
+        return;
+    }
+    //the bullet-enemy radius is drawn around enemies, the others around the ship
+    for (tr, ship) in player.iter() {
+        let pos = position(tr);
+        lines.circle(pos, radii.player_enemy, &materials.player_collider);
+        lines.circle(pos, radii.enemy_bullet_player, &materials.player_collider);
+        lines.line(pos, Vec2::new(ship.target_x, ship.target_y), &materials.target);
+        lines.circle(Vec2::new(ship.target_x, ship.target_y), 3.0, &materials.target);
+    }
+    let mut positions = HashMap::new();
+    for (entity, tr, visible) in enemies.iter() {
+        if !visible.is_visible {
+            continue;
+        }
+        let pos = position(tr);
+        lines.circle(pos, radii.bullet_enemy, &materials.collider);
+        if let Some(last) = lines.last_positions.get(&entity).cloned() {
+            lines.line(pos, pos + (pos - last) * VELOCITY_SCALE, &materials.velocity);
+        }
+        positions.insert(entity, pos);
+    }
+    lines.last_positions = positions;
+    for (tr, visible) in bullets.iter().chain(enemy_bullets.iter()) {
+        if visible.is_visible {
+            lines.circle(position(tr), 2.0, &materials.collider);
+        }
+    }
+    if let Some(tr) = overlay.selected.and_then(|entity| all.get(entity).ok()) {
+        lines.circle(position(tr), 16.0, &materials.selected);
+    }
+}
+
+//one thin sprite per segment, kept and hidden when not needed
+fn draw_lines_system(
+    mut commands: Commands,
+    mut lines: ResMut<DebugLines>,
+    mut sprites: Query<(&mut Transform, &mut Sprite, &mut Handle<ColorMaterial>, &mut Visible)>,
+) {
+    let lines = &mut *lines;
+    for (i, (from, to, material)) in lines.lines.iter().enumerate() {
+        let diff = *to - *from;
+        let center = (*from + *to) / 2.;
+        let transform = Transform {
+            translation: Vec3::new(center.x, center.y, DEBUG_Z),
+            rotation: Quat::from_rotation_z(diff.y.atan2(diff.x)),
+            ..Default::default()
+        };
+        let size = Vec2::new(diff.length().max(1.0), 1.0);
+        match lines.sprites.get(i).and_then(|e| sprites.get_mut(*e).ok()) {
+            Some((mut tr, mut sprite, mut handle, mut visible)) => {
+                *tr = transform;
+                sprite.size = size;
+                *handle = material.clone();
+                visible.is_visible = true;
+            }
+            //new sprites are drawn from the next frame
+            None if i >= lines.sprites.len() => {
+                let entity = commands
+                    .spawn()
+                    .insert_bundle(SpriteBundle {
+                        material: material.clone(),
+                        transform,
+                        sprite: Sprite::new(size),
+                        ..Default::default()
+                    })
+                    .id();
+                lines.sprites.push(entity);
+            }
+            None => {}
+        }
+    }
+    for entity in lines.sprites.iter().skip(lines.lines.len()) {
+        if let Ok((_, _, _, mut visible)) = sprites.get_mut(*entity) {
+            visible.is_visible = false;
+        }
+    }
+}
+
+fn stats_text_system(
+    overlay: Res<DebugOverlay>,
+    diagnostics: Res<Diagnostics>,
+    mut texts: Query<&mut Text, With<DebugStatsText>>,
+    entities: Query<Entity>,
+    counts: (
+        Query<&Visible, With<Enemy>>,
+        Query<&Visible, With<Bullet>>,
+        Query<&Visible, With<EnemyBullet>>,
+        Query<&Visible, With<Particle>>,
+        Query<&PowerUp>,
+        Query<&Shockwave>,
+    ),
+) {
+    let message = if overlay.enabled {
+        let fps = diagnostics
+            .get(FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.average())
+            .unwrap_or(0.0);
+        //visible / spawned, hidden ones wait in a pool
+        let (enemies, bullets, enemy_bullets, particles, power_ups, shockwaves) = counts;
+        format!(
+            "FPS {:.0}\nentities {}\nEnemy {}/{}\nBullet {}/{}\nEnemyBullet {}/{}\nParticle {}/{}\nPowerUp {}\nShockwave {}",
+            fps,
+            entities.iter().count(),
+            enemies.iter().filter(|v| v.is_visible).count(),
+            enemies.iter().count(),
+            bullets.iter().filter(|v| v.is_visible).count(),
+            bullets.iter().count(),
+            enemy_bullets.iter().filter(|v| v.is_visible).count(),
+            enemy_bullets.iter().count(),
+            particles.iter().filter(|v| v.is_visible).count(),
+            particles.iter().count(),
+            power_ups.iter().count(),
+            shockwaves.iter().count(),
+        )
+    } else {
+        String::new()
+    };
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
+
+//left click selects the nearest gameplay entity under the cursor
+fn pick_entity_system(
+    mut overlay: ResMut<DebugOverlay>,
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    candidates: Query<
+        (Entity, &Transform, &Visible),
+        Or<(With<PlayerShip>, With<Enemy>, With<Bullet>, With<EnemyBullet>, With<PowerUp>)>,
+    >,
+) {
+    if !overlay.enabled || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let window = windows.iter().next().unwrap();
+    let cursor = match window.cursor_position() {
+        Some(cursor) => cursor - Vec2::new(window.width() / 2., window.height() / 2.),
+        None => return,
+    };
+    overlay.selected = candidates
+        .iter()
+        .filter(|(_, _, visible)| visible.is_visible)
+        .map(|(entity, tr, _)| (entity, position(tr).distance(cursor)))
+        .filter(|(_, distance)| *distance < 20.0)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(entity, _)| entity);
+}
+
+//components of the selected entity, only the types the game uses are known
+fn inspector_text_system(
+    overlay: Res<DebugOverlay>,
+    mut texts: Query<&mut Text, With<DebugInspectorText>>,
+    transforms: Query<&Transform>,
+    components: (
+        Query<&PlayerShip>,
+        Query<&Weapon>,
+        Query<&Enemy>,
+        Query<&Shooter>,
+        Query<&Boss>,
+        Query<&Bullet>,
+        Query<&EnemyBullet>,
+        Query<&PowerUp>,
+    ),
+) {
+    let entity = match overlay.selected {
+        Some(entity) if overlay.enabled => entity,
+        _ => {
+            for mut text in texts.iter_mut() {
+                text.sections[0].value.clear();
+            }
+            return;
+        }
+    };
+    let (ships, weapons, enemies, shooters, bosses, bullets, enemy_bullets, power_ups) = components;
+    let mut message = format!("{:?}\n", entity);
+    if let Ok(tr) = transforms.get(entity) {
+        message.push_str(&format!("Transform ({:.1}, {:.1})\n", tr.translation.x, tr.translation.y));
+    }
+    if let Ok(ship) = ships.get(entity) {
+        message.push_str(&format!(
+            "PlayerShip target ({:.1}, {:.1}) lives {} bombs {} shield {} upgrade {}\n",
+            ship.target_x, ship.target_y, ship.lives, ship.bombs, ship.shield_frames, ship.upgrade_frames
+        ));
+    }
+    if let Ok(weapon) = weapons.get(entity) {
+        message.push_str(&format!("Weapon {:?} next shot {}\n", weapon.kind, weapon.frames_to_fire));
+    }
+    if let Ok(enemy) = enemies.get(entity) {
+        message.push_str(&format!("Enemy health {} appear {}\n", enemy.health, enemy.frames_to_appear));
+    }
+    if let Ok(shooter) = shooters.get(entity) {
+        message.push_str(&format!(
+            "Shooter {:?} enabled {} next shot {}\n",
+            shooter.pattern, shooter.enabled, shooter.frames_to_fire
+        ));
+    }
+    if let Ok(boss) = bosses.get(entity) {
+        message.push_str(&format!("Boss phase {} max health {}\n", boss.phase, boss.max_health));
+    }
+    if let Ok(bullet) = bullets.get(entity) {
+        message.push_str(&format!(
+            "Bullet {:?} velocity ({:.1}, {:.1}) damage {}\n",
+            bullet.kind, bullet.velocity.x, bullet.velocity.y, bullet.damage
+        ));
+    }
+    if let Ok(bullet) = enemy_bullets.get(entity) {
+        message.push_str(&format!(
+            "EnemyBullet velocity ({:.1}, {:.1})\n",
+            bullet.velocity.x, bullet.velocity.y
+        ));
+    }
+    if let Ok(power_up) = power_ups.get(entity) {
+        message.push_str(&format!("PowerUp {:?} expires {}\n", power_up.kind, power_up.frames_to_expire));
+    }
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+
+use crate::components::{
+    AppState, Difficulty, DifficultyLevel, EnemyKilledEvent, PlayerHitEvent, TimeScale,
+};
+use crate::console_plugin::{ConsoleAppExt, ConsoleCommandEvent, ConsoleCommands, ConsoleLog};
+use crate::profiler_plugin::timed;
+
+// Plugin
+//Adaptive difficulty: while Difficulty.adaptive is on, the spawn pressure rises when the player
+//kills fast and drops after the ship is hit. The level itself is chosen in the settings.
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<DifficultyTracker>()
+            .add_console_command("difficulty", "difficulty [easy|normal|hard]")
+            .add_console_command("adaptive", "adaptive [on|off]")
+            .add_system(console_command_system.system())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(timed(adaptive_difficulty_system.system())),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "DifficultyPlugin"
+    }
+}
+
+//game frames between adjustments of the pressure
+const ADAPT_FRAMES: i32 = 300;
+//kills per second at which the pressure stays at 1.0
+const TARGET_KILL_RATE: f32 = 0.5;
+//how much one kill per second above the target raises the pressure
+const KILL_RATE_WEIGHT: f32 = 0.5;
+//how much one recent hit lowers the pressure
+const HIT_WEIGHT: f32 = 0.3;
+//recent hits are halved every adjustment, so they are forgotten in a few
+const HIT_MEMORY: f32 = 0.5;
+//the pressure moves this part of the way to its target per adjustment
+const ADAPT_SPEED: f32 = 0.5;
+const MIN_PRESSURE: f32 = 0.6;
+const MAX_PRESSURE: f32 = 1.8;
+
+//What the player did since the last adjustment, used as Resource
+#[derive(Default)]
+struct DifficultyTracker {
+    frames: i32,
+    kills: i32,
+    hits: i32,
+    //hits of earlier adjustments, fading out
+    recent_hits: f32,
+}
+
+//pressure of a new run starts from 1.0
+fn cleanup(
+    mut difficulty: ResMut<Difficulty>,
+    mut tracker: ResMut<DifficultyTracker>,
+) {
+    difficulty.pressure = 1.0;
+    *tracker = DifficultyTracker::default();
+}
+
+fn adaptive_difficulty_system(
+    mut difficulty: ResMut<Difficulty>,
+    mut tracker: ResMut<DifficultyTracker>,
+    mut killed_events: EventReader<EnemyKilledEvent>,
+    mut hit_events: EventReader<PlayerHitEvent>,
+    time: Res<TimeScale>,
+) {
+    //read the events every frame, even when not adaptive, so old ones don't count later
+    tracker.kills += killed_events.iter().count() as i32;
+    tracker.hits += hit_events.iter().count() as i32;
+    tracker.frames += time.ticks;
+    if tracker.frames < ADAPT_FRAMES {
+        return;
+    }
+    let kill_rate = tracker.kills as f32 * 60.0 / tracker.frames as f32;
+    tracker.recent_hits = tracker.recent_hits * HIT_MEMORY + tracker.hits as f32;
+    tracker.frames = 0;
+    tracker.kills = 0;
+    tracker.hits = 0;
+    if !difficulty.adaptive {
+        return;
+    }
+    let target = 1.0 + (kill_rate - TARGET_KILL_RATE) * KILL_RATE_WEIGHT
+        - tracker.recent_hits * HIT_WEIGHT;
+    let pressure = difficulty.pressure + (target - difficulty.pressure) * ADAPT_SPEED;
+    difficulty.pressure = pressure.max(MIN_PRESSURE).min(MAX_PRESSURE);
+}
+
+fn console_command_system(
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    console_commands: Res<ConsoleCommands>,
+    mut difficulty: ResMut<Difficulty>,
+) {
+    for event in events.iter() {
+        match event.command {
+            "difficulty" => {
+                if let Some(name) = event.args.get(0) {
+                    match DifficultyLevel::parse(name) {
+                        Some(level) => difficulty.level = level,
+                        None => {
+                            log.usage(&console_commands, event);
+                            continue;
+                        }
+                    }
+                }
+                log.print(format!(
+                    "difficulty {:?}, adaptive {}, pressure {:.2}",
+                    difficulty.level,
+                    if difficulty.adaptive { "on" } else { "off" },
+                    difficulty.pressure
+                ));
+            }
+            "adaptive" => {
+                difficulty.adaptive = match event.args.get(0).map(|arg| arg.as_str()) {
+                    None => !difficulty.adaptive,
+                    Some("on") => true,
+                    Some("off") => false,
+                    Some(_) => {
+                        log.usage(&console_commands, event);
+                        continue;
+                    }
+                };
+                if !difficulty.adaptive {
+                    difficulty.pressure = 1.0;
+                }
+                log.print(if difficulty.adaptive {
+                    "adaptive difficulty on"
+                } else {
+                    "adaptive difficulty off"
+                });
+            }
+            _ => {}
+        }
+    }
+}
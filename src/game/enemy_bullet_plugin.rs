@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use crate::components::{
+    Arena, Difficulty, Enemy, EnemyBullet, FirePattern, PlayerShip, Shooter, TimeScale,
+};
+use crate::components::AppState;
+use crate::loading_plugin::GameAssets;
+use crate::pool::Pool;
+use crate::profiler_plugin::timed;
+
+// Plugin
+pub struct EnemyBulletPlugin;
+
+impl Plugin for EnemyBulletPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(timed(enemy_fire_system.system()))
+                .with_system(timed(move_enemy_bullet_system.system())),
+        );
+    }
+    fn name(&self) -> &str {
+        "EnemyBulletPlugin"
+    }
+}
+
+fn enemy_fire_system(
+    mut commands: Commands,
+    mut shooters: Query<(&Transform, &Enemy, &mut Shooter, &Visible)>,
+    player: Query<&Transform, With<PlayerShip>>,
+    mut pool: ResMut<Pool<EnemyBullet>>,
+    assets: Res<GameAssets>,
+    time: Res<TimeScale>,
+    difficulty: Res<Difficulty>,
+) {
+    let player_tr = match player.iter().next() {
+        Some(tr) => tr,
+        None => return,
+    };
+    //no game time passed in this frame
+    if time.ticks == 0 {
+        return;
+    }
+    for (tr, enemy, mut shooter, visible) in shooters.iter_mut() {
+        //not shoot until the enemy appears
+        if !shooter.enabled || !visible.is_visible || enemy.frames_to_appear > 0 {
+            continue;
+        }
+        if shooter.frames_to_fire > 0 {
+            shooter.frames_to_fire -= time.ticks;
+            continue;
+        }
+        shooter.frames_to_fire = difficulty.fire_interval(shooter.interval);
+
+        let mut directions: Vec<Vec2> = Vec::new();
+        match shooter.pattern {
+            FirePattern::Aimed => {
+                let diff = player_tr.translation - tr.translation;
+                directions.push(Vec2::new(diff.x, diff.y).normalize_or_zero());
+            }
+            FirePattern::RadialBurst(count) => {
+                for i in 0..count {
+                    let angle = std::f32::consts::PI * 2. * i as f32 / count as f32;
+                    directions.push(Vec2::new(angle.cos(), angle.sin()));
+                }
+            }
+            FirePattern::Spiral => {
+                shooter.angle += 0.4;
+                directions.push(Vec2::new(shooter.angle.cos(), shooter.angle.sin()));
+            }
+        }
+        for dir in directions {
+            pool.spawn(&mut commands)
+                .insert_bundle(SpriteBundle {
+                    material: assets.enemy_bullet_material.clone(),
+                    transform: Transform::from_translation(tr.translation),
+                    sprite: Sprite::new(Vec2::new(8.0, 8.0)),
+                    ..Default::default()
+                }).insert(
+                EnemyBullet {
+                    velocity: dir * shooter.bullet_speed,
+                }
+            );
+        }
+    }
+}
+
+fn move_enemy_bullet_system(
+    mut commands: Commands,
+    mut bullets: Query<(Entity, &mut Transform, &EnemyBullet, &mut Visible)>,
+    mut pool: ResMut<Pool<EnemyBullet>>,
+    arena: Res<Arena>,
+    time: Res<TimeScale>,
+) {
+    let half_w = arena.width / 2.;
+    let half_h = arena.height / 2.;
+
+    for (entity, mut tr, bullet, mut visible) in bullets.iter_mut() {
+        if !visible.is_visible {
+            continue;
+        }
+        tr.translation.x += bullet.velocity.x * time.delta;
+        tr.translation.y += bullet.velocity.y * time.delta;
+        let tr = &tr.translation;
+        if tr.x < -half_w || tr.x > half_w || tr.y < -half_h || tr.y > half_h {
+            if !pool.release(entity, &mut visible) {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
@@ -0,0 +1,522 @@
+use bevy::prelude::*;
+
+use crate::components::{
+    Arena, Boss, BossHealthBar, BossPart, Bullet, Difficulty, Enemy, FirePattern, GameRng,
+    GlobalEnemyState, PlayerShip, Shooter, Steering, TimeScale, Waves, HIT,
+};
+use crate::components::AppState;
+use crate::console_plugin::{ConsoleAppExt, ConsoleCommandEvent, ConsoleCommands, ConsoleLog};
+use crate::loading_plugin::GameAssets;
+use crate::pool::Pool;
+use crate::profiler_plugin::timed;
+use crate::spatial::SpatialGrid;
+
+// Plugin
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Waves::default())
+            .init_resource::<Steering>()
+            .add_console_command("spawn enemy", "spawn enemy [homing|aimed|radial|spiral] [count]")
+            .add_console_command("spawn boss", "spawn boss")
+            .add_console_command("wave", "wave <number>")
+            .add_system(console_command_system.system())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(timed(spawn_enemy_system.system()))
+                    .with_system(timed(move_enemy_system.system()))
+                    .with_system(timed(move_boss_system.system()))
+                    .with_system(timed(boss_phase_system.system()))
+                    .with_system(timed(boss_health_bar_system.system())),
+            )
+            //enemies themselves are released by PoolPlugin
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "EnemyPlugin"
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    mut global_state: ResMut<GlobalEnemyState>,
+    bars: Query<Entity, With<BossHealthBar>>,
+) {
+    *global_state = GlobalEnemyState::default();
+    for entity in bars.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn spawn_enemy_system(
+    mut commands: Commands,
+    mut global_state: ResMut<GlobalEnemyState>,
+    waves: Res<Waves>,
+    player: Query<&Transform, With<PlayerShip>>,
+    enemies: Query<&Visible, With<Enemy>>,
+    mut pool: ResMut<Pool<Enemy>>,
+    arena: Res<Arena>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+    time: Res<TimeScale>,
+    difficulty: Res<Difficulty>,
+) {
+    //no game time passed in this frame
+    if time.ticks == 0 {
+        return;
+    }
+    let wave = waves.get(global_state.wave);
+    if global_state.spawned_in_wave >= wave.enemy_count {
+        //wait until the wave is cleared
+        if enemies.iter().any(|visible| visible.is_visible) {
+            return;
+        }
+        if wave.boss && !global_state.boss_spawned {
+            global_state.boss_spawned = true;
+            spawn_boss(&mut commands, &arena, &assets, &difficulty);
+            return;
+        }
+        //next wave
+        global_state.wave += 1;
+        global_state.spawned_in_wave = 0;
+        global_state.boss_spawned = false;
+        global_state.frames_to_next_enemy = 120;
+        return;
+    }
+    if global_state.frames_to_next_enemy > 0 {
+        global_state.frames_to_next_enemy -= time.ticks;
+        return
+    }
+    global_state.spawn_interval = difficulty.spawn_interval(wave.spawn_interval);
+    global_state.frames_to_next_enemy = global_state.spawn_interval;
+    global_state.spawned_in_wave += 1;
+
+    let player_tr = match player.iter().next() {
+        Some(tr) => tr,
+        None => return,
+    };
+    //some enemies can shoot. Every pooled enemy has a Shooter to keep the same components.
+    let pattern = choose_fire_pattern(rng.next_f32());
+    let transform = create_enemy_position(&player_tr, &arena, &mut rng);
+    spawn_enemy(&mut commands, &mut pool, &assets, &difficulty, transform, pattern);
+}
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    pool: &mut Pool<Enemy>,
+    assets: &GameAssets,
+    difficulty: &Difficulty,
+    transform: Transform,
+    pattern: Option<FirePattern>,
+) {
+    let enemy = Enemy::default();
+    let shooter = match pattern {
+        Some(pattern) => Shooter::new(pattern),
+        None => Shooter::disabled(),
+    };
+    pool.spawn(commands)
+        .insert_bundle(SpriteBundle {
+            material: assets.enemy_material.clone(),
+            transform,
+            sprite: Sprite::new(Vec2::new(30.0, 30.0)),
+            ..Default::default()
+        })
+        .insert(Enemy {
+            health: difficulty.enemy_health(enemy.health),
+            ..enemy
+        })
+        .insert(shooter);
+}
+fn choose_fire_pattern(value: f32) -> Option<FirePattern> {
+    if value < 0.2 {
+        Some(FirePattern::Aimed)
+    } else if value < 0.3 {
+        Some(FirePattern::RadialBurst(8))
+    } else if value < 0.35 {
+        Some(FirePattern::Spiral)
+    } else {
+        None
+    }
+}
+//random positions tried before giving up on one away from the ship
+const MAX_POSITION_TRIES: usize = 100;
+
+fn create_enemy_position(
+    player_transform: &Transform,
+    arena: &Arena,
+    rng: &mut GameRng,
+) -> Transform {
+    let px = player_transform.translation.x;
+    let py = player_transform.translation.y;
+
+    for _ in 0..MAX_POSITION_TRIES {
+        let x = rng.next_f32() * arena.width - arena.width / 2.;
+        let y = rng.next_f32() * arena.height - arena.height / 2.;
+        //avoid near place from player, to avoid immediate-collision
+        let dx = px - x;
+        let dy = py - y;
+        if dx * dx + dy * dy >= 400.0 {
+            return Transform::from_translation(Vec3::new(x, y, 0.));
+        }
+    }
+    //no luck, e.g. the ship fills a small arena: the corner farthest from it
+    let x = if px > 0. { -arena.width / 2. } else { arena.width / 2. };
+    let y = if py > 0. { -arena.height / 2. } else { arena.height / 2. };
+    Transform::from_translation(Vec3::new(x, y, 0.))
+}
+
+//enemies closer than this steer each other
+const NEIGHBOUR_RADIUS: f32 = 40.0;
+//only the first neighbours found are counted, enough for a smooth flock in a dense swarm
+const MAX_NEIGHBOURS: usize = 12;
+//the player's bullets closer than this are fled from
+const FLEE_RADIUS: f32 = 60.0;
+//part of the way the velocity turns to the desired one in a frame
+const STEER_RATE: f32 = 0.1;
+//per frame, on Normal
+const ENEMY_SPEED: f32 = 0.5;
+
+//Kept between frames by move_enemy_system, so the grids reuse their memory
+struct SteeringState {
+    enemies: SpatialGrid,
+    bullets: SpatialGrid,
+    //position and velocity of the moving enemies, in query order
+    boids: Vec<(Vec2, Vec2)>,
+}
+impl Default for SteeringState {
+    fn default() -> Self {
+        SteeringState {
+            enemies: SpatialGrid::new(NEIGHBOUR_RADIUS),
+            bullets: SpatialGrid::new(FLEE_RADIUS),
+            boids: Vec::new(),
+        }
+    }
+}
+
+//Enemies flock: they keep apart, follow their neighbours, head for the player and dodge bullets.
+//How much of each depends on the archetype, see Steering.
+fn move_enemy_system(
+    mut queries: QuerySet<(
+        Query<(&mut Transform, &mut Enemy, &Shooter, &Visible), (Without<Boss>, Without<BossPart>)>,
+        Query<&Transform, With<PlayerShip>>,
+        Query<(&Transform, &Visible), With<Bullet>>,
+    )>,
+    steering: Res<Steering>,
+    time: Res<TimeScale>,
+    difficulty: Res<Difficulty>,
+    mut state: Local<SteeringState>,
+) {
+    let base_speed = ENEMY_SPEED * difficulty.level.spec().enemy_speed;
+    let player = match queries.q1().iter().next() {
+        Some(tr) => tr.translation.truncate(),
+        None => return,
+    };
+
+    //where everyone is before anyone moves
+    let state = &mut *state;
+    state.enemies.clear();
+    state.bullets.clear();
+    state.boids.clear();
+    for (tr, enemy, _, visible) in queries.q0().iter() {
+        if !visible.is_visible {
+            continue;
+        }
+        let position = tr.translation.truncate();
+        state.enemies.insert(state.boids.len(), position);
+        state.boids.push((position, enemy.velocity));
+    }
+    for (index, (tr, visible)) in queries.q2().iter().enumerate() {
+        if visible.is_visible {
+            state.bullets.insert(index, tr.translation.truncate());
+        }
+    }
+
+    let boids = &state.boids;
+    let mut index = 0;
+    for (mut tr, mut enemy, shooter, visible) in queries.q0_mut().iter_mut() {
+        if !visible.is_visible {
+            continue;
+        }
+        let me = index;
+        index += 1;
+        let (position, velocity) = boids[me];
+        enemy.frames_to_appear = (enemy.frames_to_appear - time.ticks).max(0);
+
+        let mut separation = Vec2::ZERO;
+        let mut heading = Vec2::ZERO;
+        let mut center = Vec2::ZERO;
+        let mut count = 0;
+        state.enemies.for_each_near(position, NEIGHBOUR_RADIUS, |other, other_position, distance| {
+            if other == me || count >= MAX_NEIGHBOURS {
+                return;
+            }
+            count += 1;
+            //pushed harder by closer neighbours
+            if distance > 0.0 {
+                separation += (position - other_position) / (distance * distance);
+            }
+            heading += boids[other].1;
+            center += other_position;
+        });
+        let mut flee = Vec2::ZERO;
+        state.bullets.for_each_near(position, FLEE_RADIUS, |_, bullet, distance| {
+            if distance > 0.0 {
+                flee += (position - bullet) / distance;
+            }
+        });
+
+        let weights = steering.weights(shooter);
+        let mut desired = (player - position).normalize_or_zero() * weights.seek
+            + separation.normalize_or_zero() * weights.separation
+            + flee.normalize_or_zero() * weights.flee;
+        if count > 0 {
+            desired += (heading / count as f32).normalize_or_zero() * weights.alignment;
+            desired += (center / count as f32 - position).normalize_or_zero() * weights.cohesion;
+        }
+        let max_speed = base_speed * weights.speed;
+        let target = desired.clamp_length_max(1.0) * max_speed;
+        let turn = (STEER_RATE * time.delta).min(1.0);
+        enemy.velocity = (velocity + (target - velocity) * turn).clamp_length_max(max_speed);
+        tr.translation.x += enemy.velocity.x * time.delta;
+        tr.translation.y += enemy.velocity.y * time.delta;
+    }
+}
+
+//returns the boss and its parts
+pub fn spawn_boss(
+    commands: &mut Commands,
+    arena: &Arena,
+    assets: &GameAssets,
+    difficulty: &Difficulty,
+) -> (Entity, Vec<Entity>) {
+    let half_h = arena.height / 2.;
+    let max_health = difficulty.enemy_health(200 * HIT);
+    let boss = commands
+        .spawn()
+        .insert_bundle(SpriteBundle {
+            material: assets.boss_material.clone(),
+            transform: Transform::from_translation(Vec3::new(0., half_h / 2., 0.)),
+            sprite: Sprite::new(Vec2::new(60.0, 60.0)),
+            ..Default::default()
+        })
+        .insert(Enemy {
+            frames_to_appear: 60,
+            health: max_health,
+            ..Default::default()
+        })
+        .insert(Boss { max_health, phase: 0, frames: 0 })
+        .insert(Shooter::new(boss_fire_pattern(0)))
+        .id();
+    //two turrets on both sides
+    let mut parts = Vec::new();
+    for x in [-45.0, 45.0].iter() {
+        let part = commands
+            .spawn()
+            .insert_bundle(SpriteBundle {
+                material: assets.boss_part_material.clone(),
+                transform: Transform::from_translation(Vec3::new(*x, half_h / 2., 0.)),
+                sprite: Sprite::new(Vec2::new(25.0, 25.0)),
+                ..Default::default()
+            })
+            .insert(Enemy {
+                frames_to_appear: 60,
+                health: difficulty.enemy_health(30 * HIT),
+                ..Default::default()
+            })
+            .insert(BossPart { boss, offset: Vec2::new(*x, 0.) })
+            .insert(Shooter::new(FirePattern::RadialBurst(6)))
+            .id();
+        parts.push(part);
+    }
+    //health bar at the top of the arena
+    commands
+        .spawn()
+        .insert_bundle(SpriteBundle {
+            material: assets.boss_health_bar_material.clone(),
+            transform: Transform::from_translation(Vec3::new(0., half_h - 20., 1.)),
+            sprite: Sprite::new(Vec2::new(300.0, 6.0)),
+            ..Default::default()
+        })
+        .insert(BossHealthBar);
+    (boss, parts)
+}
+
+fn boss_fire_pattern(phase: usize) -> FirePattern {
+    match phase {
+        0 => FirePattern::Aimed,
+        1 => FirePattern::Spiral,
+        _ => FirePattern::RadialBurst(16),
+    }
+}
+
+//sway at the upper side of the arena, faster in later phases.
+//move_enemy_system leaves the boss out, so its appearing is counted here.
+fn move_boss_system(
+    mut bosses: Query<(Entity, &mut Transform, &mut Boss, &mut Enemy)>,
+    mut parts: Query<(Entity, &mut Transform, &BossPart, &mut Enemy), Without<Boss>>,
+    mut commands: Commands,
+    arena: Res<Arena>,
+    time: Res<TimeScale>,
+) {
+    let half_w = arena.width / 2.;
+    let half_h = arena.height / 2.;
+    for (_, mut tr, mut boss, mut enemy) in bosses.iter_mut() {
+        enemy.frames_to_appear = (enemy.frames_to_appear - time.ticks).max(0);
+        let speed = 1 + boss.phase as i32;
+        boss.frames += speed * time.ticks;
+        //the fraction keeps the sway smooth in slow motion
+        let frames = boss.frames as f32 + speed as f32 * time.fraction;
+        tr.translation.x = (frames * 0.01).sin() * half_w / 2.;
+        tr.translation.y = half_h / 2.;
+    }
+    for (entity, mut tr, part, mut enemy) in parts.iter_mut() {
+        enemy.frames_to_appear = (enemy.frames_to_appear - time.ticks).max(0);
+        match bosses.get_mut(part.boss) {
+            Ok((_, boss_tr, _, _)) => {
+                tr.translation.x = boss_tr.translation.x + part.offset.x;
+                tr.translation.y = boss_tr.translation.y + part.offset.y;
+            }
+            //the boss was destroyed, parts go with it
+            Err(_) => commands.entity(entity).despawn(),
+        }
+    }
+}
+
+fn boss_phase_system(
+    mut bosses: Query<(&Enemy, &mut Boss, &mut Shooter)>,
+) {
+    for (enemy, mut boss, mut shooter) in bosses.iter_mut() {
+        let ratio = enemy.health as f32 / boss.max_health as f32;
+        let phase = if ratio > 2. / 3. {
+            0
+        } else if ratio > 1. / 3. {
+            1
+        } else {
+            2
+        };
+        if phase != boss.phase {
+            boss.phase = phase;
+            *shooter = Shooter::new(boss_fire_pattern(phase));
+        }
+    }
+}
+
+fn boss_health_bar_system(
+    mut commands: Commands,
+    bosses: Query<(&Enemy, &Boss)>,
+    mut bars: Query<(Entity, &mut Sprite), With<BossHealthBar>>,
+) {
+    match bosses.iter().next() {
+        Some((enemy, boss)) => {
+            let ratio = enemy.health.max(0) as f32 / boss.max_health as f32;
+            for (_, mut sprite) in bars.iter_mut() {
+                sprite.size.x = 300.0 * ratio;
+            }
+        }
+        None => {
+            for (entity, _) in bars.iter_mut() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+//a larger count would stall the game for seconds
+const MAX_CONSOLE_SPAWN: u32 = 500;
+
+fn console_command_system(
+    mut commands: Commands,
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    console_commands: Res<ConsoleCommands>,
+    mut global_state: ResMut<GlobalEnemyState>,
+    player: Query<&Transform, With<PlayerShip>>,
+    mut pool: ResMut<Pool<Enemy>>,
+    arena: Res<Arena>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+    difficulty: Res<Difficulty>,
+) {
+    for event in events.iter() {
+        match event.command {
+            "spawn enemy" => {
+                let pattern = match event.args.get(0).map(|arg| arg.as_str()) {
+                    //enemies without a gun just chase the player
+                    None | Some("homing") | Some("plain") => None,
+                    Some("aimed") => Some(FirePattern::Aimed),
+                    Some("radial") => Some(FirePattern::RadialBurst(8)),
+                    Some("spiral") => Some(FirePattern::Spiral),
+                    Some(_) => {
+                        log.usage(&console_commands, event);
+                        continue;
+                    }
+                };
+                let count = match event.args.get(1) {
+                    None => 1,
+                    Some(_) => match event.arg::<u32>(1) {
+                        Some(count) => count.min(MAX_CONSOLE_SPAWN),
+                        None => {
+                            log.usage(&console_commands, event);
+                            continue;
+                        }
+                    },
+                };
+                let player_tr = match player.iter().next() {
+                    Some(tr) => tr,
+                    None => continue,
+                };
+                for _ in 0..count {
+                    let transform = create_enemy_position(player_tr, &arena, &mut rng);
+                    spawn_enemy(
+                        &mut commands,
+                        &mut pool,
+                        &assets,
+                        &difficulty,
+                        transform,
+                        pattern,
+                    );
+                }
+                log.print(format!("spawned {} enemies", count));
+            }
+            "spawn boss" => {
+                spawn_boss(&mut commands, &arena, &assets, &difficulty);
+                log.print("spawned a boss");
+            }
+            "wave" => match event.arg::<usize>(0) {
+                Some(wave) if wave >= 1 => {
+                    //start the wave from its first enemy, enemies on screen stay
+                    global_state.wave = wave - 1;
+                    global_state.spawned_in_wave = 0;
+                    global_state.boss_spawned = false;
+                    global_state.frames_to_next_enemy = 120;
+                    log.print(format!("wave {}", wave));
+                }
+                _ => log.usage(&console_commands, event),
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::MIN_ARENA_SIZE;
+
+    #[test]
+    fn enemy_position_is_found_in_a_tiny_arena() {
+        //smaller than GameCorePlugin allows, no random point is 20 away from the ship
+        let arena = Arena { width: 10., height: 10. };
+        let player = Transform::from_translation(Vec3::new(1., 1., 0.));
+        let mut rng = GameRng::new(7);
+        let tr = create_enemy_position(&player, &arena, &mut rng);
+        assert_eq!(tr.translation, Vec3::new(-5., -5., 0.));
+    }
+
+    #[test]
+    fn small_arenas_are_raised_to_the_minimum() {
+        let arena = Arena::new(10., f32::NAN);
+        assert_eq!(arena.width, MIN_ARENA_SIZE);
+        assert_eq!(arena.height, MIN_ARENA_SIZE);
+    }
+}
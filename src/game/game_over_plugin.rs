@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::components::{
+    AppState, AttractMode, DevOptions, EnemyKilledEvent, GameOverScreen, GameOverText, GameRng,
+    GlobalEnemyState, PlayerShip, Score, TimeScale,
+};
+use crate::console_plugin::{ConsoleAppExt, ConsoleCommandEvent, ConsoleCommands, ConsoleLog};
+use crate::high_score_plugin::{HighScoreEntry, HighScoreTable};
+use crate::loading_plugin::GameAssets;
+use crate::menu_plugin::{take_button, take_key};
+use crate::storage::today;
+use crate::GameConfig;
+
+// Plugin
+//Counts the score and ends the run when the ship has no lives left.
+//A score good enough for the high score table asks for the player's initials.
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        //the table is shared with HighScorePlugin, whichever is added first loads it
+        if !app.world_mut().contains_resource::<HighScoreTable>() {
+            app.insert_resource(HighScoreTable::load());
+        }
+        app.init_resource::<InitialsEntry>()
+            .add_console_command("seed", "seed <number>|random")
+            .add_system(console_command_system.system())
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_score.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(score_system.system())
+                    .with_system(game_over_check_system.system()),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::GameOver)
+                    .with_system(initials_input_system.system())
+                    .with_system(game_over_text_system.system()),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "GameOverPlugin"
+    }
+}
+
+const POINTS_PER_ENEMY: u32 = 100;
+
+//Initials being typed on the game over screen, used as Resource
+pub struct InitialsEntry {
+    //false if the score doesn't enter the table
+    active: bool,
+    letters: [u8; 3],
+    cursor: usize,
+}
+impl Default for InitialsEntry {
+    fn default() -> Self {
+        InitialsEntry {
+            active: false,
+            letters: *b"AAA",
+            cursor: 0,
+        }
+    }
+}
+
+//a new run with a new seed, a resumed run replaces them afterwards
+fn reset_score(
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+    options: Res<DevOptions>,
+) {
+    let seed = options.seed.unwrap_or_else(random::<u64>);
+    *score = Score {
+        points: 0,
+        wave: 1,
+        seed,
+    };
+    *rng = GameRng::new(seed);
+}
+
+//the seed is used from now on and by every restarted run
+fn console_command_system(
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    console_commands: Res<ConsoleCommands>,
+    mut options: ResMut<DevOptions>,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+) {
+    for event in events.iter() {
+        if event.command != "seed" {
+            continue;
+        }
+        if event.args.get(0).map(|arg| arg.as_str()) == Some("random") {
+            options.seed = None;
+            log.print("random seed for the next runs");
+            continue;
+        }
+        match event.arg::<u64>(0) {
+            Some(seed) => {
+                options.seed = Some(seed);
+                score.seed = seed;
+                *rng = GameRng::new(seed);
+                log.print(format!("seed {}, restart to replay the run from the start", seed));
+            }
+            None => log.usage(&console_commands, event),
+        }
+    }
+}
+
+fn score_system(
+    mut score: ResMut<Score>,
+    mut events: EventReader<EnemyKilledEvent>,
+    enemy_state: Res<GlobalEnemyState>,
+) {
+    score.points += events.iter().count() as u32 * POINTS_PER_ENEMY;
+    //kept here because GlobalEnemyState is reset when InGame is exited
+    score.wave = enemy_state.wave as u32 + 1;
+}
+
+fn game_over_check_system(
+    player: Query<&PlayerShip>,
+    attract: Res<AttractMode>,
+    mut state: ResMut<State<AppState>>,
+    time: Res<TimeScale>,
+) {
+    //the last hit is shown in slow motion first
+    if !player.iter().any(|ship| ship.lives <= 0) || time.slow_motion_frames > 0 {
+        return;
+    }
+    //the demo just goes back to the title screen.
+    //When pausing or the console queued a transition in the same frame, it's tried again later.
+    let next = if attract.active { AppState::Title } else { AppState::GameOver };
+    let _ = state.set(next);
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    score: Res<Score>,
+    table: Res<HighScoreTable>,
+    mut entry: ResMut<InitialsEntry>,
+) {
+    *entry = InitialsEntry {
+        active: table.qualifies(score.points),
+        ..Default::default()
+    };
+    commands
+        .spawn()
+        .insert_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(GameOverScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                        TextAlignment {
+                            horizontal: HorizontalAlign::Center,
+                            ..Default::default()
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(GameOverText);
+        });
+}
+
+fn cleanup(
+    mut commands: Commands,
+    screens: Query<Entity, With<GameOverScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+//the letter `step` places after this one, wrapping from Z to A
+fn next_letter(letter: u8, step: u8) -> u8 {
+    b'A' + (letter - b'A' + step) % 26
+}
+
+//letters can be typed, or chosen with Up/Down and Left/Right like an arcade cabinet
+fn initials_input_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    mut entry: ResMut<InitialsEntry>,
+    mut table: ResMut<HighScoreTable>,
+    score: Res<Score>,
+    mut state: ResMut<State<AppState>>,
+    config: Res<GameConfig>,
+) {
+    let confirm = take_key(&mut keys, KeyCode::Return)
+        | take_button(&mut buttons, GamepadButtonType::South);
+    if !entry.active {
+        if confirm {
+            state.set(config.home_state()).unwrap();
+        }
+        return;
+    }
+    for c in chars.iter().map(|e| e.char.to_ascii_uppercase()) {
+        if c.is_ascii_uppercase() {
+            let cursor = entry.cursor;
+            entry.letters[cursor] = c as u8;
+            entry.cursor = (cursor + 1).min(2);
+        }
+    }
+    let cursor = entry.cursor;
+    if take_key(&mut keys, KeyCode::Up) | take_button(&mut buttons, GamepadButtonType::DPadUp) {
+        entry.letters[cursor] = next_letter(entry.letters[cursor], 1);
+    }
+    if take_key(&mut keys, KeyCode::Down) | take_button(&mut buttons, GamepadButtonType::DPadDown) {
+        entry.letters[cursor] = next_letter(entry.letters[cursor], 25);
+    }
+    if take_key(&mut keys, KeyCode::Left)
+        | take_key(&mut keys, KeyCode::Back)
+        | take_button(&mut buttons, GamepadButtonType::DPadLeft)
+    {
+        entry.cursor = cursor.saturating_sub(1);
+    }
+    if take_key(&mut keys, KeyCode::Right) | take_button(&mut buttons, GamepadButtonType::DPadRight) {
+        entry.cursor = (cursor + 1).min(2);
+    }
+    if confirm {
+        table.insert(HighScoreEntry {
+            initials: String::from_utf8_lossy(&entry.letters).to_string(),
+            points: score.points,
+            date: today(),
+            wave: score.wave,
+            seed: score.seed,
+        });
+        table.save();
+        entry.active = false;
+        state.set(config.home_state()).unwrap();
+    }
+}
+
+fn game_over_text_system(
+    entry: Res<InitialsEntry>,
+    score: Res<Score>,
+    mut texts: Query<&mut Text, With<GameOverText>>,
+) {
+    let mut message = format!("GAME OVER\n\nScore: {}  Wave: {}\n\n", score.points, score.wave);
+    if entry.active {
+        message.push_str("NEW HIGH SCORE\nEnter your initials\n\n");
+        for (i, letter) in entry.letters.iter().enumerate() {
+            if i == entry.cursor {
+                message.push_str(&format!("[{}]", *letter as char));
+            } else {
+                message.push_str(&format!(" {} ", *letter as char));
+            }
+        }
+    } else {
+        message.push_str("Press Enter");
+    }
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
@@ -0,0 +1,317 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::components::{AppState, HighScoreScreen};
+use crate::loading_plugin::GameAssets;
+use crate::menu_plugin::{
+    spawn_menu, MenuEvent, MenuId, MenuInput, MenuMaterials, MenuSelection,
+};
+use crate::storage::{backup_broken_file, data_file, read_file, write_file};
+
+// Plugin
+//High score screen, pushed on top of the title screen.
+//The table is loaded from the user's data directory when the app starts.
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        //GameOverPlugin may have loaded it already
+        if !app.world_mut().contains_resource::<HighScoreTable>() {
+            app.insert_resource(HighScoreTable::load());
+        }
+        app.add_system_set(SystemSet::on_enter(AppState::HighScores).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::HighScores)
+                    .with_system(high_score_menu_system.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::HighScores).with_system(cleanup.system()),
+            );
+    }
+    fn name(&self) -> &str {
+        "HighScorePlugin"
+    }
+}
+
+const FILE_NAME: &str = "highscores.txt";
+//first line of the file, the number is increased when the format changes
+const FILE_HEADER: &str = "highscores";
+const FILE_VERSION: u32 = 1;
+//entries kept in the table
+pub const MAX_ENTRIES: usize = 10;
+
+pub struct HighScoreEntry {
+    pub initials: String,
+    pub points: u32,
+    //YYYY-MM-DD
+    pub date: String,
+    pub wave: u32,
+    pub seed: u64,
+}
+
+impl HighScoreEntry {
+    //one line of the file: initials points date wave seed
+    fn to_line(&self) -> String {
+        format!("{} {} {} {} {}", self.initials, self.points, self.date, self.wave, self.seed)
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            return None;
+        }
+        let initials = fields[0];
+        if initials.len() != 3 || !initials.chars().all(|c| c.is_ascii_uppercase()) {
+            return None;
+        }
+        Some(HighScoreEntry {
+            initials: initials.to_string(),
+            points: fields[1].parse().ok()?,
+            date: fields[2].to_string(),
+            wave: fields[3].parse().ok()?,
+            seed: fields[4].parse().ok()?,
+        })
+    }
+}
+
+//Best scores, highest first, used as Resource
+#[derive(Default)]
+pub struct HighScoreTable {
+    pub entries: Vec<HighScoreEntry>,
+    //None when there is nowhere to save
+    path: Option<PathBuf>,
+}
+
+impl HighScoreTable {
+    //a missing file is an empty table, a broken one is kept as .bak and the table starts empty
+    pub fn load() -> Self {
+        HighScoreTable::load_from(data_file(FILE_NAME))
+    }
+
+    //the table saved at path, None for a table that is never saved
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let text = match path.as_ref().and_then(read_file) {
+            Some(text) => text,
+            None => return HighScoreTable { entries: Vec::new(), path },
+        };
+        match parse(&text) {
+            Some(entries) => HighScoreTable { entries, path },
+            None => {
+                if let Some(path) = path.as_ref() {
+                    backup_broken_file(path);
+                }
+                HighScoreTable { entries: Vec::new(), path }
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = match self.path.as_ref() {
+            Some(path) => path,
+            None => return,
+        };
+        write_file(path, &self.to_text());
+    }
+
+    //the content of the file
+    fn to_text(&self) -> String {
+        let mut text = format!("{} {}\n", FILE_HEADER, FILE_VERSION);
+        for entry in self.entries.iter() {
+            text.push_str(&entry.to_line());
+            text.push('\n');
+        }
+        text
+    }
+
+    //true if the points would enter the table
+    pub fn qualifies(&self, points: u32) -> bool {
+        points > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.iter().any(|entry| points > entry.points))
+    }
+
+    //add an entry at its rank, dropping the last one if the table is full
+    pub fn insert(&mut self, entry: HighScoreEntry) {
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| entry.points > e.points)
+            .unwrap_or_else(|| self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+//None if the file is broken
+fn parse(text: &str) -> Option<Vec<HighScoreEntry>> {
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines.next()?.split_whitespace().collect();
+    if header.len() != 2 || header[0] != FILE_HEADER {
+        return None;
+    }
+    let version: u32 = header[1].parse().ok()?;
+    if version != FILE_VERSION {
+        return None;
+    }
+    let mut entries = Vec::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        entries.push(HighScoreEntry::from_line(line)?);
+    }
+    entries.sort_by(|a, b| b.points.cmp(&a.points));
+    entries.truncate(MAX_ENTRIES);
+    Some(entries)
+}
+
+fn table_text(table: &HighScoreTable) -> String {
+    let mut text = String::from("HIGH SCORES\n\n");
+    if table.entries.is_empty() {
+        text.push_str("No scores yet");
+    }
+    for (rank, entry) in table.entries.iter().enumerate() {
+        text.push_str(&format!(
+            "{:>2}. {} {:>8}  wave {:>2}  {}\n",
+            rank + 1,
+            entry.initials,
+            entry.points,
+            entry.wave,
+            entry.date
+        ));
+    }
+    text
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    materials: Res<MenuMaterials>,
+    mut selection: ResMut<MenuSelection>,
+    table: Res<HighScoreTable>,
+) {
+    let root = spawn_menu(
+        &mut commands,
+        &assets,
+        &materials,
+        &mut selection,
+        MenuId::HighScores,
+        &table_text(&table),
+        &["Back".to_string()],
+    );
+    commands.entity(root).insert(HighScoreScreen);
+}
+
+fn cleanup(
+    mut commands: Commands,
+    screens: Query<Entity, With<HighScoreScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn high_score_menu_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut events: EventReader<MenuEvent>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let input = MenuInput::read(&mut keys, &mut buttons);
+    let back = events.iter().any(|event| event.menu == MenuId::HighScores);
+    if input.back || back {
+        state.pop().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn entry(initials: &str, points: u32) -> HighScoreEntry {
+        HighScoreEntry {
+            initials: initials.to_string(),
+            points,
+            date: "2021-05-01".to_string(),
+            wave: 3,
+            seed: 42,
+        }
+    }
+
+    //a file under the temp directory, never the user's data directory
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("highscores-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("bak"));
+        path
+    }
+
+    #[test]
+    fn saved_table_is_read_back() {
+        let mut table = HighScoreTable::default();
+        table.insert(entry("BBB", 200));
+        table.insert(entry("AAA", 300));
+        let entries = parse(&table.to_text()).unwrap();
+        let lines: Vec<String> = entries.iter().map(|e| e.to_line()).collect();
+        assert_eq!(lines, vec!["AAA 300 2021-05-01 3 42", "BBB 200 2021-05-01 3 42"]);
+    }
+
+    #[test]
+    fn empty_lines_are_skipped() {
+        let entries = parse("highscores 1\n\nAAA 10 2021-05-01 1 7\n\n").unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn broken_files_are_rejected() {
+        assert!(parse("").is_none());
+        assert!(parse("not a high score file\n").is_none());
+        assert!(parse("highscores\n").is_none());
+        assert!(parse("highscores x\n").is_none());
+        //initials must be 3 capital letters
+        assert!(parse("highscores 1\nabc 10 2021-05-01 1 7\n").is_none());
+        //points must be a number
+        assert!(parse("highscores 1\nAAA ten 2021-05-01 1 7\n").is_none());
+        //a line cut in the middle
+        assert!(parse("highscores 1\nAAA 10 2021-05-01\n").is_none());
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let text = format!("highscores {}\nAAA 10 2021-05-01 1 7\n", FILE_VERSION + 1);
+        assert!(parse(&text).is_none());
+    }
+
+    #[test]
+    fn extra_entries_are_dropped() {
+        let mut text = format!("highscores {}\n", FILE_VERSION);
+        for points in 1..=(MAX_ENTRIES as u32 + 5) {
+            text.push_str(&format!("AAA {} 2021-05-01 1 7\n", points));
+        }
+        let entries = parse(&text).unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        //the best ones are kept, highest first
+        assert_eq!(entries[0].points, MAX_ENTRIES as u32 + 5);
+        assert_eq!(entries[MAX_ENTRIES - 1].points, 6);
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_table() {
+        let path = temp_file("missing.txt");
+        let table = HighScoreTable::load_from(Some(path.clone()));
+        assert!(table.entries.is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn broken_file_is_kept_as_backup() {
+        let path = temp_file("broken.txt");
+        fs::write(&path, "garbage").unwrap();
+        let table = HighScoreTable::load_from(Some(path.clone()));
+        assert!(table.entries.is_empty());
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(path.with_extension("bak")).unwrap(), "garbage");
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use crate::components::{AppState, GlobalEnemyState, HudText, PlayerShip, Score, Weapon};
+use crate::loading_plugin::GameAssets;
+
+// Plugin
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(SystemSet::on_enter(AppState::InGame).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame).with_system(update_hud_system.system()),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "HudPlugin"
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+) {
+    commands
+        .spawn()
+        .insert_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(4.0),
+                    left: Val::Px(6.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 14.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        }).insert(
+        HudText
+    );
+}
+
+fn cleanup(
+    mut commands: Commands,
+    hud: Query<Entity, With<HudText>>,
+) {
+    for entity in hud.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn update_hud_system(
+    player: Query<(&PlayerShip, &Weapon)>,
+    mut hud: Query<&mut Text, With<HudText>>,
+    enemy_state: Res<GlobalEnemyState>,
+    score: Res<Score>,
+) {
+    if let Some((ship, weapon)) = player.iter().next() {
+        for mut text in hud.iter_mut() {
+            text.sections[0].value = format!(
+                "Score: {}  Wave: {}  Lives: {}  Bombs: {}  Weapon: {:?}",
+                score.points, enemy_state.wave + 1, ship.lives, ship.bombs, weapon.kind
+            );
+        }
+    }
+}
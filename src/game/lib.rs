@@ -0,0 +1,371 @@
+use bevy::app::{Events, PluginGroupBuilder};
+use bevy::prelude::*;
+
+pub mod player_ship_plugin;
+pub mod bullet_plugin;
+pub mod components;
+pub mod enemy_plugin;
+pub mod collision_plugin;
+pub mod pickup_plugin;
+pub mod bomb_plugin;
+pub mod hud_plugin;
+pub mod enemy_bullet_plugin;
+pub mod particle_plugin;
+pub mod pool;
+pub mod loading_plugin;
+pub mod audio_plugin;
+pub mod menu_plugin;
+pub mod pause_plugin;
+pub mod settings_plugin;
+pub mod title_plugin;
+pub mod high_score_plugin;
+pub mod game_over_plugin;
+pub mod storage;
+pub mod save_plugin;
+pub mod debug_plugin;
+pub mod console_plugin;
+pub mod time_scale_plugin;
+pub mod profiler_plugin;
+pub mod difficulty_plugin;
+pub mod spatial;
+
+use crate::player_ship_plugin::PlayerShipPlugin;
+use crate::bullet_plugin::BulletPlugin;
+use crate::enemy_plugin::EnemyPlugin;
+use crate::collision_plugin::CollisionPlugin;
+use crate::pickup_plugin::PickupPlugin;
+use crate::bomb_plugin::BombPlugin;
+use crate::hud_plugin::HudPlugin;
+use crate::enemy_bullet_plugin::EnemyBulletPlugin;
+use crate::particle_plugin::ParticlePlugin;
+use crate::pool::PoolPlugin;
+use crate::loading_plugin::LoadingPlugin;
+use crate::components::{
+    AppState, Arena, AttractMode, DevOptions, Difficulty, DifficultyLevel, EnemyKilledEvent,
+    GameRng, GlobalEnemyState, PlayerHitEvent, Score, SoundEvent,
+};
+use crate::audio_plugin::GameAudioPlugin;
+use crate::menu_plugin::MenuPlugin;
+use crate::pause_plugin::PausePlugin;
+use crate::settings_plugin::{Settings, SettingsPlugin};
+use crate::title_plugin::TitlePlugin;
+use crate::high_score_plugin::HighScorePlugin;
+use crate::game_over_plugin::GameOverPlugin;
+use crate::save_plugin::SavePlugin;
+use crate::debug_plugin::DebugPlugin;
+use crate::console_plugin::ConsolePlugin;
+use crate::time_scale_plugin::TimeScalePlugin;
+use crate::profiler_plugin::ProfilerPlugin;
+use crate::difficulty_plugin::DifficultyPlugin;
+
+//Which parts of the game GamePlugins adds, and how they are set up. Used as Resource too.
+//Loading, pools and the time scale are always added, the other parts are opted into.
+//
+//  app.add_plugins(GamePlugins(GameConfig::new().player_ship().bullets().enemies().collision()));
+//  app.add_plugins(GamePlugins(GameConfig { hud: false, ..GameConfig::full() }));
+//  app.add_plugins(GamePlugins(GameConfig::full().difficulty(DifficultyLevel::Hard)));
+//
+//Any of the parts can be left out, the resources and events they share are added by the core.
+#[derive(Clone, Debug)]
+pub struct GameConfig {
+    pub initial_state: AppState,
+    //fixed size of the play area, None follows the window.
+    //Either way it's at least MIN_ARENA_SIZE wide and high.
+    pub arena: Option<Vec2>,
+    //changed by the player on the settings screen when SettingsPlugin is added
+    pub difficulty: DifficultyLevel,
+    pub adaptive_difficulty: bool,
+    pub player_ship: bool,
+    pub bullets: bool,
+    pub enemies: bool,
+    pub collision: bool,
+    pub pickups: bool,
+    pub particles: bool,
+    pub game_over: bool,
+    pub hud: bool,
+    pub audio: bool,
+    pub menus: bool,
+    pub save: bool,
+    pub dev_tools: bool,
+    pub profiler: bool,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig::full()
+    }
+}
+
+impl GameConfig {
+    //nothing but the core, starting with the loading screen
+    pub fn new() -> Self {
+        GameConfig {
+            initial_state: AppState::Loading,
+            arena: None,
+            difficulty: DifficultyLevel::Normal,
+            adaptive_difficulty: false,
+            player_ship: false,
+            bullets: false,
+            enemies: false,
+            collision: false,
+            pickups: false,
+            particles: false,
+            game_over: false,
+            hud: false,
+            audio: false,
+            menus: false,
+            save: false,
+            dev_tools: false,
+            profiler: false,
+        }
+    }
+
+    //every part, the whole game as tutorial07 runs it
+    pub fn full() -> Self {
+        GameConfig::new()
+            .gameplay()
+            .hud()
+            .audio()
+            .menus()
+            .save()
+            .dev_tools()
+            .profiler()
+    }
+
+    //everything needed to play a run, without screens around it
+    pub fn gameplay(self) -> Self {
+        self.player_ship()
+            .bullets()
+            .enemies()
+            .collision()
+            .pickups()
+            .particles()
+            .game_over()
+    }
+
+    //e.g. InGame to skip the loading screen and the title
+    pub fn initial_state(mut self, state: AppState) -> Self {
+        self.initial_state = state;
+        self
+    }
+
+    //sizes below MIN_ARENA_SIZE are raised to it
+    pub fn arena(mut self, width: f32, height: f32) -> Self {
+        self.arena = Some(Vec2::new(width, height));
+        self
+    }
+
+    pub fn difficulty(mut self, level: DifficultyLevel) -> Self {
+        self.difficulty = level;
+        self
+    }
+
+    pub fn adaptive_difficulty(mut self) -> Self {
+        self.adaptive_difficulty = true;
+        self
+    }
+
+    pub fn player_ship(mut self) -> Self {
+        self.player_ship = true;
+        self
+    }
+
+    pub fn bullets(mut self) -> Self {
+        self.bullets = true;
+        self
+    }
+
+    //enemies and their bullets
+    pub fn enemies(mut self) -> Self {
+        self.enemies = true;
+        self
+    }
+
+    pub fn collision(mut self) -> Self {
+        self.collision = true;
+        self
+    }
+
+    //power-ups and bombs
+    pub fn pickups(mut self) -> Self {
+        self.pickups = true;
+        self
+    }
+
+    pub fn particles(mut self) -> Self {
+        self.particles = true;
+        self
+    }
+
+    //score, game over screen and initials entry
+    pub fn game_over(mut self) -> Self {
+        self.game_over = true;
+        self
+    }
+
+    pub fn hud(mut self) -> Self {
+        self.hud = true;
+        self
+    }
+
+    pub fn audio(mut self) -> Self {
+        self.audio = true;
+        self
+    }
+
+    //title, pause, settings and high score screens
+    pub fn menus(mut self) -> Self {
+        self.menus = true;
+        self
+    }
+
+    pub fn save(mut self) -> Self {
+        self.save = true;
+        self
+    }
+
+    //debug overlay and developer console
+    pub fn dev_tools(mut self) -> Self {
+        self.dev_tools = true;
+        self
+    }
+
+    pub fn profiler(mut self) -> Self {
+        self.profiler = true;
+        self
+    }
+
+    //where loading and the end of a run lead, the title screen comes with the menus
+    pub fn home_state(&self) -> AppState {
+        if self.menus {
+            AppState::Title
+        } else {
+            AppState::InGame
+        }
+    }
+}
+
+//The game as one plugin group, e.g. app.add_plugins(GamePlugins::default()).
+//Single plugins can still be left out with add_plugins_with and disable.
+#[derive(Default)]
+pub struct GamePlugins(pub GameConfig);
+
+impl PluginGroup for GamePlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        let config = &self.0;
+        group.add(GameCorePlugin(config.clone()))
+            .add(LoadingPlugin);
+        if config.audio {
+            group.add(GameAudioPlugin);
+        }
+        group.add(PoolPlugin)
+            .add(TimeScalePlugin);
+        if config.player_ship {
+            group.add(PlayerShipPlugin);
+        }
+        if config.bullets {
+            group.add(BulletPlugin);
+        }
+        if config.enemies {
+            //presets and spawn pressure, the kills and hits it adapts to are events of the core
+            group.add(EnemyPlugin)
+                .add(EnemyBulletPlugin)
+                .add(DifficultyPlugin);
+        }
+        if config.collision {
+            group.add(CollisionPlugin);
+        }
+        if config.pickups {
+            group.add(PickupPlugin)
+                .add(BombPlugin);
+        }
+        if config.hud {
+            group.add(HudPlugin);
+        }
+        if config.particles {
+            group.add(ParticlePlugin);
+        }
+        if config.menus {
+            group.add(MenuPlugin)
+                .add(PausePlugin)
+                .add(SettingsPlugin)
+                .add(TitlePlugin)
+                .add(HighScorePlugin);
+        }
+        if config.game_over {
+            group.add(GameOverPlugin);
+        }
+        if config.save {
+            group.add(SavePlugin);
+        }
+        if config.dev_tools {
+            group.add(DebugPlugin)
+                .add(ConsolePlugin);
+        }
+        if config.profiler {
+            group.add(ProfilerPlugin);
+        }
+    }
+}
+
+// Plugin
+//The state and the resources every part reads, even when the part adding them is left out.
+pub struct GameCorePlugin(pub GameConfig);
+
+impl Plugin for GameCorePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let config = self.0.clone();
+        let arena = match config.arena {
+            Some(size) => {
+                let arena = Arena::new(size.x, size.y);
+                if arena.width != size.x || arena.height != size.y {
+                    warn!(
+                        "arena {} x {} is too small, using {} x {}",
+                        size.x, size.y, arena.width, arena.height
+                    );
+                }
+                arena
+            }
+            None => match app.world_mut().get_resource::<WindowDescriptor>() {
+                Some(window) => Arena::new(window.width, window.height),
+                None => Arena::default(),
+            },
+        };
+        if config.arena.is_none() {
+            app.add_system_to_stage(CoreStage::PreUpdate, arena_system.system());
+        }
+        if !app.world_mut().contains_resource::<Events<SoundEvent>>() {
+            app.add_event::<SoundEvent>();
+        }
+        //must be added before plugins using AppState
+        app.add_state(config.initial_state)
+            .add_event::<EnemyKilledEvent>()
+            .add_event::<PlayerHitEvent>()
+            .insert_resource(arena)
+            .insert_resource(Difficulty::new(config.difficulty, config.adaptive_difficulty))
+            .init_resource::<Settings>()
+            .init_resource::<AttractMode>()
+            .init_resource::<DevOptions>()
+            .init_resource::<Score>()
+            .init_resource::<GameRng>()
+            .init_resource::<GlobalEnemyState>()
+            .insert_resource(config);
+    }
+    fn name(&self) -> &str {
+        "GameCorePlugin"
+    }
+}
+
+//the arena follows the size of the primary window
+fn arena_system(
+    windows: Res<Windows>,
+    mut arena: ResMut<Arena>,
+) {
+    if let Some(window) = windows.get_primary() {
+        //a minimized window has no size
+        let size = Arena::new(window.width(), window.height());
+        if arena.width != size.width || arena.height != size.height {
+            *arena = size;
+        }
+    }
+}
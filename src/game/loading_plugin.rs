@@ -0,0 +1,313 @@
+use bevy::asset::{HandleId, LoadState};
+use bevy::prelude::*;
+
+use crate::components::{AppState, LoadingScreen, PowerUpKind, WeaponKind};
+use crate::GameConfig;
+
+// Plugin
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<GameAssets>()
+            .init_resource::<LoadErrors>()
+            .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::Loading).with_system(check_assets_system.system()),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Loading).with_system(cleanup.system()))
+            .add_system_set(
+                SystemSet::on_enter(AppState::LoadError).with_system(show_error_system.system()),
+            );
+    }
+    fn name(&self) -> &str {
+        "LoadingPlugin"
+    }
+}
+
+//Textures, font and materials shared by every spawn, used as Resource.
+//Materials are created once here instead of calling materials.add for each entity.
+pub struct GameAssets {
+    pub triangle: Handle<Texture>,
+    pub elipse: Handle<Texture>,
+    pub circle: Handle<Texture>,
+    pub font: Handle<Font>,
+    pub player_material: Handle<ColorMaterial>,
+    pub enemy_material: Handle<ColorMaterial>,
+    pub boss_material: Handle<ColorMaterial>,
+    pub boss_part_material: Handle<ColorMaterial>,
+    pub boss_health_bar_material: Handle<ColorMaterial>,
+    pub enemy_bullet_material: Handle<ColorMaterial>,
+    pub shockwave_material: Handle<ColorMaterial>,
+    weapon_materials: Vec<Handle<ColorMaterial>>,
+    power_up_materials: Vec<Handle<ColorMaterial>>,
+    //every file to wait for before starting the game
+    manifest: Vec<(&'static str, HandleId)>,
+}
+
+//Marker for the bar filled while loading
+pub struct LoadingProgressBar;
+
+//Paths that failed to load, used as Resource in LoadError
+#[derive(Default)]
+pub struct LoadErrors(pub Vec<&'static str>);
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        let triangle: Handle<Texture> = asset_server.load(TRIANGLE_PATH);
+        let elipse: Handle<Texture> = asset_server.load(ELIPSE_PATH);
+        let circle: Handle<Texture> = asset_server.load(CIRCLE_PATH);
+        let font: Handle<Font> = asset_server.load(FONT_PATH);
+        let manifest = vec![
+            (TRIANGLE_PATH, triangle.id),
+            (ELIPSE_PATH, elipse.id),
+            (CIRCLE_PATH, circle.id),
+            (FONT_PATH, font.id),
+        ];
+
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        let weapon_materials = WEAPONS
+            .iter()
+            .map(|kind| {
+                let spec = kind.spec();
+                let texture = match spec.texture {
+                    "triangle.png" => triangle.clone(),
+                    "circle.png" => circle.clone(),
+                    _ => elipse.clone(),
+                };
+                materials.add(ColorMaterial::modulated_texture(texture, spec.color))
+            })
+            .collect();
+        let power_up_materials = POWER_UPS
+            .iter()
+            .map(|kind| {
+                materials.add(ColorMaterial::modulated_texture(circle.clone(), power_up_color(*kind)))
+            })
+            .collect();
+        GameAssets {
+            player_material: materials.add(triangle.clone().into()),
+            enemy_material: materials.add(circle.clone().into()),
+            boss_material: materials.add(ColorMaterial::modulated_texture(circle.clone(), Color::PURPLE)),
+            boss_part_material: materials.add(ColorMaterial::modulated_texture(circle.clone(), Color::VIOLET)),
+            boss_health_bar_material: materials.add(Color::RED.into()),
+            enemy_bullet_material: materials.add(ColorMaterial::modulated_texture(circle.clone(), Color::PINK)),
+            shockwave_material: materials.add(ColorMaterial::modulated_texture(
+                circle.clone(),
+                Color::rgba(1.0, 1.0, 1.0, 0.4),
+            )),
+            weapon_materials,
+            power_up_materials,
+            manifest,
+            triangle,
+            elipse,
+            circle,
+            font,
+        }
+    }
+}
+
+const TRIANGLE_PATH: &str = "triangle.png";
+const ELIPSE_PATH: &str = "elipse.png";
+const CIRCLE_PATH: &str = "circle.png";
+const FONT_PATH: &str = "fonts/DejaVuSansMono.ttf";
+
+pub const WEAPONS: [WeaponKind; 5] = [
+    WeaponKind::Spread,
+    WeaponKind::Rapid,
+    WeaponKind::Laser,
+    WeaponKind::Homing,
+    WeaponKind::Bouncing,
+];
+
+pub const POWER_UPS: [PowerUpKind; 4] = [
+    PowerUpKind::WeaponUpgrade,
+    PowerUpKind::Shield,
+    PowerUpKind::ExtraLife,
+    PowerUpKind::Bomb,
+];
+
+fn power_up_color(kind: PowerUpKind) -> Color {
+    match kind {
+        PowerUpKind::WeaponUpgrade => Color::ORANGE,
+        PowerUpKind::Shield => Color::CYAN,
+        PowerUpKind::ExtraLife => Color::GREEN,
+        PowerUpKind::Bomb => Color::RED,
+    }
+}
+
+impl GameAssets {
+    pub fn weapon_material(&self, kind: WeaponKind) -> Handle<ColorMaterial> {
+        let index = WEAPONS.iter().position(|k| *k == kind).unwrap();
+        self.weapon_materials[index].clone()
+    }
+
+    pub fn power_up_material(&self, kind: PowerUpKind) -> Handle<ColorMaterial> {
+        let index = POWER_UPS.iter().position(|k| *k == kind).unwrap();
+        self.power_up_materials[index].clone()
+    }
+
+    pub fn manifest(&self) -> &[(&'static str, HandleId)] {
+        &self.manifest
+    }
+
+    //wait for a file loaded by another plugin too, e.g. the sounds
+    pub fn add_to_manifest(&mut self, path: &'static str, id: HandleId) {
+        self.manifest.push((path, id));
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    //centered label and progress bar
+    commands
+        .spawn()
+        .insert_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(LoadingScreen)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    "Loading...",
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(200.0), Val::Px(10.0)),
+                        margin: Rect::all(Val::Px(8.0)),
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::DARK_GRAY.into()),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            material: materials.add(Color::WHITE.into()),
+                            ..Default::default()
+                        })
+                        .insert(LoadingProgressBar);
+                });
+        });
+}
+
+fn check_assets_system(
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    mut errors: ResMut<LoadErrors>,
+    mut state: ResMut<State<AppState>>,
+    mut bars: Query<&mut Style, With<LoadingProgressBar>>,
+    config: Res<GameConfig>,
+) {
+    let mut loaded = 0;
+    let mut failed: Vec<&'static str> = Vec::new();
+    for (path, id) in assets.manifest() {
+        match asset_server.get_load_state(*id) {
+            LoadState::Loaded => loaded += 1,
+            LoadState::Failed => failed.push(*path),
+            _ => {}
+        }
+    }
+    let total = assets.manifest().len();
+    for mut style in bars.iter_mut() {
+        style.size.width = Val::Percent(loaded as f32 * 100.0 / total as f32);
+    }
+
+    if !failed.is_empty() {
+        errors.0 = failed;
+        state.set(AppState::LoadError).unwrap();
+    } else if loaded == total {
+        state.set(config.home_state()).unwrap();
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<LoadingScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+//the game can't start, show which files are broken
+fn show_error_system(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    errors: Res<LoadErrors>,
+    mut windows: ResMut<Windows>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut message = String::from("Failed to load assets:\n");
+    for path in errors.0.iter() {
+        error!("failed to load asset: {}", path);
+        message.push_str(&format!("  assets/{}\n", path));
+    }
+    message.push_str("Check the files and restart the game.");
+    //without the font the text can't be drawn, show a red screen and the paths in the title
+    if errors.0.contains(&FONT_PATH) {
+        if let Some(window) = windows.get_primary_mut() {
+            let paths: Vec<String> =
+                errors.0.iter().map(|path| format!("assets/{}", path)).collect();
+            window.set_title(format!("Failed to load assets: {}", paths.join(", ")));
+        }
+        commands
+            .spawn()
+            .insert_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    ..Default::default()
+                },
+                material: materials.add(Color::MAROON.into()),
+                ..Default::default()
+            });
+        return;
+    }
+    commands
+        .spawn()
+        .insert_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                message,
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 14.0,
+                    color: Color::ORANGE_RED,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        });
+}
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+
+use crate::loading_plugin::GameAssets;
+
+// Plugin
+//Shared menu widgets, navigable by mouse, keyboard and gamepad.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<MenuMaterials>()
+            .init_resource::<MenuSelection>()
+            .add_event::<MenuEvent>()
+            .add_system(menu_navigation_system.system().label(MENU_NAVIGATION))
+            .add_system(menu_mouse_system.system())
+            .add_system(menu_highlight_system.system());
+    }
+    fn name(&self) -> &str {
+        "MenuPlugin"
+    }
+}
+
+//Label of the system moving the selection, for screens that must read keys before it
+pub const MENU_NAVIGATION: &str = "menu_navigation";
+
+//Which menu an item belongs to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuId {
+    Title,
+    Pause,
+    Settings,
+    HighScores,
+}
+
+//One button of a menu
+pub struct MenuItem {
+    pub menu: MenuId,
+    pub index: usize,
+}
+
+//The text in a menu button, to change it later
+pub struct MenuLabel {
+    pub index: usize,
+}
+
+//Sent when a menu item is chosen
+pub struct MenuEvent {
+    pub menu: MenuId,
+    pub index: usize,
+}
+
+//The highlighted item of the menu on screen, used as Resource
+#[derive(Default)]
+pub struct MenuSelection(pub usize);
+
+pub struct MenuMaterials {
+    pub background: Handle<ColorMaterial>,
+    pub normal: Handle<ColorMaterial>,
+    pub selected: Handle<ColorMaterial>,
+}
+
+impl FromWorld for MenuMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        MenuMaterials {
+            background: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.7).into()),
+            normal: materials.add(Color::rgb(0.15, 0.15, 0.2).into()),
+            selected: materials.add(Color::rgb(0.3, 0.3, 0.6).into()),
+        }
+    }
+}
+
+//Input a screen with a menu handles itself, moving the selection is done by MenuPlugin
+#[derive(Default)]
+pub struct MenuInput {
+    pub left: bool,
+    pub right: bool,
+    pub back: bool,
+}
+
+//true once for a key, it's reset so that a screen opened in the same frame doesn't see it again
+pub fn take_key(keys: &mut Input<KeyCode>, code: KeyCode) -> bool {
+    if keys.just_pressed(code) {
+        keys.reset(code);
+        return true;
+    }
+    false
+}
+
+//same as take_key, for any connected gamepad
+pub fn take_button(buttons: &mut Input<GamepadButton>, kind: GamepadButtonType) -> bool {
+    let pressed: Vec<GamepadButton> = buttons
+        .get_just_pressed()
+        .filter(|button| button.1 == kind)
+        .cloned()
+        .collect();
+    for button in pressed.iter() {
+        buttons.reset(*button);
+    }
+    !pressed.is_empty()
+}
+
+impl MenuInput {
+    pub fn read(keys: &mut Input<KeyCode>, buttons: &mut Input<GamepadButton>) -> Self {
+        MenuInput {
+            left: take_key(keys, KeyCode::Left)
+                | take_key(keys, KeyCode::A)
+                | take_button(buttons, GamepadButtonType::DPadLeft),
+            right: take_key(keys, KeyCode::Right)
+                | take_key(keys, KeyCode::D)
+                | take_button(buttons, GamepadButtonType::DPadRight),
+            back: take_key(keys, KeyCode::Escape)
+                | take_button(buttons, GamepadButtonType::East)
+                | take_button(buttons, GamepadButtonType::Start),
+        }
+    }
+}
+
+//spawn a centered menu and return the root entity, the caller adds its own marker to it
+pub fn spawn_menu(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    materials: &MenuMaterials,
+    selection: &mut MenuSelection,
+    menu: MenuId,
+    title: &str,
+    items: &[String],
+) -> Entity {
+    selection.0 = 0;
+    commands
+        .spawn()
+        .insert_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.background.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect::all(Val::Px(8.0)),
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    title,
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            for (index, item) in items.iter().enumerate() {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(180.0), Val::Px(22.0)),
+                            margin: Rect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: materials.normal.clone(),
+                        ..Default::default()
+                    })
+                    .insert(MenuItem { menu, index })
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(TextBundle {
+                                text: Text::with_section(
+                                    item.as_str(),
+                                    TextStyle {
+                                        font: assets.font.clone(),
+                                        font_size: 14.0,
+                                        color: Color::WHITE,
+                                    },
+                                    Default::default(),
+                                ),
+                                ..Default::default()
+                            })
+                            .insert(MenuLabel { index });
+                    });
+            }
+        })
+        .id()
+}
+
+fn menu_navigation_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut selection: ResMut<MenuSelection>,
+    items: Query<&MenuItem>,
+    mut events: EventWriter<MenuEvent>,
+) {
+    let menu = match items.iter().next() {
+        Some(item) => item.menu,
+        None => return,
+    };
+    let count = items.iter().count();
+    //the keys are taken so the screen opened by a choice doesn't see them in the same frame
+    let up = take_key(&mut keys, KeyCode::Up)
+        | take_key(&mut keys, KeyCode::W)
+        | take_button(&mut buttons, GamepadButtonType::DPadUp);
+    let down = take_key(&mut keys, KeyCode::Down)
+        | take_key(&mut keys, KeyCode::S)
+        | take_button(&mut buttons, GamepadButtonType::DPadDown);
+    let select = take_key(&mut keys, KeyCode::Return)
+        | take_key(&mut keys, KeyCode::Space)
+        | take_button(&mut buttons, GamepadButtonType::South);
+    if up {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if down {
+        selection.0 = (selection.0 + 1) % count;
+    }
+    if select {
+        events.send(MenuEvent { menu, index: selection.0 });
+    }
+}
+
+fn menu_mouse_system(
+    mut selection: ResMut<MenuSelection>,
+    items: Query<(&Interaction, &MenuItem), Changed<Interaction>>,
+    mut events: EventWriter<MenuEvent>,
+) {
+    for (interaction, item) in items.iter() {
+        match interaction {
+            Interaction::Hovered => selection.0 = item.index,
+            Interaction::Clicked => {
+                selection.0 = item.index;
+                events.send(MenuEvent { menu: item.menu, index: item.index });
+            }
+            Interaction::None => {}
+        }
+    }
+}
+
+fn menu_highlight_system(
+    selection: Res<MenuSelection>,
+    materials: Res<MenuMaterials>,
+    mut items: Query<(&MenuItem, &mut Handle<ColorMaterial>)>,
+) {
+    for (item, mut material) in items.iter_mut() {
+        *material = if item.index == selection.0 {
+            materials.selected.clone()
+        } else {
+            materials.normal.clone()
+        };
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::components::{
+    AppState, EnemyKilledEvent, Particle, PlayerHitEvent, PlayerShip, TimeScale,
+};
+use crate::loading_plugin::GameAssets;
+use crate::pool::Pool;
+use crate::profiler_plugin::timed;
+
+// Plugin
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup.system())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(timed(emit_effects_system.system()))
+                    .with_system(timed(thrust_trail_system.system()))
+                    .with_system(timed(update_particle_system.system())),
+            );
+    }
+    fn name(&self) -> &str {
+        "ParticlePlugin"
+    }
+}
+
+//all particles are spawned at startup and recycled
+const POOL_SIZE: usize = 512;
+
+//How particles of one effect look
+struct Emission {
+    count: usize,
+    speed: f32,
+    drag: f32,
+    lifetime: i32,
+    start_color: Color,
+    end_color: Color,
+    start_size: f32,
+    end_size: f32,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut pool: ResMut<Pool<Particle>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    assets: Res<GameAssets>,
+) {
+    let texture = assets.circle.clone();
+    for _ in 0..POOL_SIZE {
+        //each particle needs its own material to change the color
+        let entity = commands
+            .spawn()
+            .insert_bundle(SpriteBundle {
+                material: materials.add(ColorMaterial::texture(texture.clone())),
+                sprite: Sprite::new(Vec2::ZERO),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(Particle::default())
+            .id();
+        pool.add_free(entity);
+    }
+}
+
+fn emit(
+    pool: &mut Pool<Particle>,
+    particles: &mut Query<(&mut Particle, &mut Transform, &mut Visible)>,
+    position: Vec3,
+    direction: Option<Vec2>,
+    emission: &Emission,
+) {
+    for _ in 0..emission.count {
+        //when the pool is empty, the effect is just smaller
+        let entity = match pool.acquire() {
+            Some(entity) => entity,
+            None => return,
+        };
+        if let Ok((mut particle, mut tr, mut visible)) = particles.get_mut(entity) {
+            let dir = match direction {
+                //spread a little around the given direction
+                Some(dir) => Quat::from_rotation_z((random::<f32>() - 0.5) * 0.6)
+                    * Vec3::new(dir.x, dir.y, 0.),
+                None => {
+                    let angle = random::<f32>() * std::f32::consts::PI * 2.;
+                    Vec3::new(angle.cos(), angle.sin(), 0.)
+                }
+            };
+            let speed = emission.speed * (0.5 + random::<f32>() * 0.5);
+            *particle = Particle {
+                velocity: Vec2::new(dir.x, dir.y) * speed,
+                drag: emission.drag,
+                age: 0,
+                lifetime: emission.lifetime,
+                start_color: emission.start_color,
+                end_color: emission.end_color,
+                start_size: emission.start_size,
+                end_size: emission.end_size,
+            };
+            //behind other sprites
+            tr.translation = Vec3::new(position.x, position.y, -1.);
+            visible.is_visible = true;
+        }
+    }
+}
+
+fn emit_effects_system(
+    mut pool: ResMut<Pool<Particle>>,
+    mut particles: Query<(&mut Particle, &mut Transform, &mut Visible)>,
+    mut killed_events: EventReader<EnemyKilledEvent>,
+    mut hit_events: EventReader<PlayerHitEvent>,
+) {
+    for event in killed_events.iter() {
+        emit(&mut pool, &mut particles, event.position, None, &Emission {
+            count: 16,
+            speed: 3.0,
+            drag: 0.92,
+            lifetime: 40,
+            start_color: Color::ORANGE,
+            end_color: Color::rgba(1.0, 0.0, 0.0, 0.0),
+            start_size: 6.0,
+            end_size: 1.0,
+        });
+    }
+    for event in hit_events.iter() {
+        emit(&mut pool, &mut particles, event.position, None, &Emission {
+            count: 24,
+            speed: 4.0,
+            drag: 0.9,
+            lifetime: 50,
+            start_color: Color::WHITE,
+            end_color: Color::rgba(0.3, 0.6, 1.0, 0.0),
+            start_size: 5.0,
+            end_size: 1.0,
+        });
+    }
+}
+
+//exhaust behind the ship while it's moving
+fn thrust_trail_system(
+    mut pool: ResMut<Pool<Particle>>,
+    mut particles: Query<(&mut Particle, &mut Transform, &mut Visible)>,
+    player: Query<(&Transform, &PlayerShip), Without<Particle>>,
+    time: Res<TimeScale>,
+) {
+    for (tr, ship) in player.iter() {
+        //one particle per frame of game time, not to pile up in slow motion
+        if !ship.moving || time.ticks == 0 {
+            continue;
+        }
+        let backward = tr.rotation * -Vec3::Y;
+        let position = tr.translation + backward * 12.;
+        emit(&mut pool, &mut particles, position, Some(Vec2::new(backward.x, backward.y)), &Emission {
+            count: 1,
+            speed: 1.5,
+            drag: 0.95,
+            lifetime: 20,
+            start_color: Color::YELLOW,
+            end_color: Color::rgba(1.0, 0.2, 0.0, 0.0),
+            start_size: 4.0,
+            end_size: 0.5,
+        });
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    Color::rgba(
+        from.r() + (to.r() - from.r()) * t,
+        from.g() + (to.g() - from.g()) * t,
+        from.b() + (to.b() - from.b()) * t,
+        from.a() + (to.a() - from.a()) * t,
+    )
+}
+
+fn update_particle_system(
+    mut pool: ResMut<Pool<Particle>>,
+    mut particles: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visible,
+        &Handle<ColorMaterial>,
+    )>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<TimeScale>,
+) {
+    for (entity, mut particle, mut tr, mut sprite, mut visible, material) in particles.iter_mut() {
+        if !visible.is_visible {
+            continue;
+        }
+        particle.age += time.ticks;
+        if particle.age >= particle.lifetime {
+            //back to the pool
+            pool.release(entity, &mut visible);
+            continue;
+        }
+        tr.translation.x += particle.velocity.x * time.delta;
+        tr.translation.y += particle.velocity.y * time.delta;
+        let drag = particle.drag.powf(time.delta);
+        particle.velocity *= drag;
+
+        let t = (particle.age as f32 + time.fraction) / particle.lifetime as f32;
+        let size = particle.start_size + (particle.end_size - particle.start_size) * t;
+        sprite.size = Vec2::new(size, size);
+        if let Some(material) = materials.get_mut(material) {
+            material.color = lerp_color(particle.start_color, particle.end_color, t);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::app::{AppExit, Events};
+use bevy::prelude::*;
+
+use crate::components::{AppState, AttractMode, PauseMenu};
+use crate::loading_plugin::GameAssets;
+use crate::menu_plugin::{
+    spawn_menu, take_button, take_key, MenuEvent, MenuId, MenuInput, MenuMaterials, MenuSelection,
+};
+use crate::save_plugin::SaveRunEvent;
+use crate::settings_plugin::Settings;
+
+// Plugin
+//Escape, the pause key (P) or the gamepad Start button pause the game.
+//Paused is pushed on top of InGame, so every InGame system stops while the scene is still drawn.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        //sent to SavePlugin, nobody reads it when the run isn't saved
+        if !app.world_mut().contains_resource::<Events<SaveRunEvent>>() {
+            app.add_event::<SaveRunEvent>();
+        }
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame).with_system(pause_input_system.system()),
+        )
+        .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(setup.system()))
+        //hidden while the settings screen is open on top of it
+        .add_system_set(SystemSet::on_resume(AppState::Paused).with_system(setup.system()))
+        .add_system_set(SystemSet::on_update(AppState::Paused).with_system(pause_menu_system.system()))
+        .add_system_set(SystemSet::on_pause(AppState::Paused).with_system(cleanup.system()))
+        .add_system_set(SystemSet::on_exit(AppState::Paused).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "PausePlugin"
+    }
+}
+
+const RESUME: usize = 0;
+const RESTART: usize = 1;
+const SETTINGS: usize = 2;
+const MAIN_MENU: usize = 3;
+const QUIT: usize = 4;
+
+fn pause_input_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut state: ResMut<State<AppState>>,
+    attract: Res<AttractMode>,
+    settings: Res<Settings>,
+) {
+    //any key ends the demo instead
+    if attract.active {
+        return;
+    }
+    let pressed = take_key(&mut keys, KeyCode::Escape)
+        | take_key(&mut keys, settings.keys.pause)
+        | take_button(&mut buttons, GamepadButtonType::Start);
+    //another transition may be queued in the same frame (game over, console), it wins
+    if pressed {
+        let _ = state.push(AppState::Paused);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    materials: Res<MenuMaterials>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    let items = ["Resume", "Restart", "Settings", "Main Menu", "Quit"]
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>();
+    let root = spawn_menu(
+        &mut commands,
+        &assets,
+        &materials,
+        &mut selection,
+        MenuId::Pause,
+        "PAUSED",
+        &items,
+    );
+    commands.entity(root).insert(PauseMenu);
+}
+
+fn cleanup(
+    mut commands: Commands,
+    menus: Query<Entity, With<PauseMenu>>,
+) {
+    for entity in menus.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pause_menu_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut events: EventReader<MenuEvent>,
+    mut state: ResMut<State<AppState>>,
+    mut exit: EventWriter<AppExit>,
+    mut save_events: EventWriter<SaveRunEvent>,
+    settings: Res<Settings>,
+) {
+    let input = MenuInput::read(&mut keys, &mut buttons);
+    if input.back || take_key(&mut keys, settings.keys.pause) {
+        state.pop().unwrap();
+        return;
+    }
+    for event in events.iter() {
+        if event.menu != MenuId::Pause {
+            continue;
+        }
+        match event.index {
+            RESUME => state.pop().unwrap(),
+            //InGame is exited, every plugin cleans up, and InGame is entered again
+            RESTART => state.replace(AppState::InGame).unwrap(),
+            SETTINGS => state.push(AppState::Settings).unwrap(),
+            //the run is saved when Paused is exited, it can be continued from the title screen
+            MAIN_MENU => {
+                save_events.send(SaveRunEvent);
+                state.replace(AppState::Title).unwrap();
+            }
+            QUIT => {
+                save_events.send(SaveRunEvent);
+                state.replace(AppState::Title).unwrap();
+                exit.send(AppExit);
+            }
+            _ => {}
+        }
+        return;
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::components::{
+    AppState, DropTable, EnemyKilledEvent, GameRng, PlayerShip, PowerUp, PowerUpKind, Sound,
+    SoundEvent, TimeScale,
+};
+use crate::loading_plugin::GameAssets;
+use crate::profiler_plugin::timed;
+
+// Plugin
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(DropTable::default())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(timed(drop_power_up_system.system()))
+                    .with_system(timed(move_power_up_system.system()))
+                    .with_system(timed(collect_power_up_system.system())),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "PickupPlugin"
+    }
+}
+
+//frames for timed effects
+const SHIELD_FRAMES: i32 = 600;
+const UPGRADE_FRAMES: i32 = 900;
+
+fn cleanup(
+    mut commands: Commands,
+    power_ups: Query<Entity, With<PowerUp>>,
+) {
+    for entity in power_ups.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn drop_power_up_system(
+    mut commands: Commands,
+    mut events: EventReader<EnemyKilledEvent>,
+    drop_table: Res<DropTable>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+) {
+    for event in events.iter() {
+        let kind = match drop_table.choose(rng.next_f32()) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        //drift slowly to a random direction
+        let angle = rng.next_f32() * std::f32::consts::PI * 2.;
+        let velocity = Vec2::new(angle.cos(), angle.sin()) * 0.3;
+        commands
+            .spawn()
+            .insert_bundle(SpriteBundle {
+                material: assets.power_up_material(kind),
+                transform: Transform::from_translation(event.position),
+                sprite: Sprite::new(Vec2::new(14.0, 14.0)),
+                ..Default::default()
+            }).insert(
+            PowerUp {
+                kind,
+                velocity,
+                frames_to_expire: drop_table.frames_to_expire,
+            }
+        );
+    }
+}
+
+fn move_power_up_system(
+    mut commands: Commands,
+    mut power_ups: Query<(Entity, &mut Transform, &mut PowerUp, &mut Visible)>,
+    time: Res<TimeScale>,
+) {
+    for (entity, mut tr, mut power_up, mut visible) in power_ups.iter_mut() {
+        tr.translation.x += power_up.velocity.x * time.delta;
+        tr.translation.y += power_up.velocity.y * time.delta;
+        power_up.frames_to_expire -= time.ticks;
+        if power_up.frames_to_expire <= 0 {
+            commands.entity(entity).despawn();
+        } else if power_up.frames_to_expire < 120 {
+            //blink before it disappears
+            visible.is_visible = power_up.frames_to_expire % 10 < 5;
+        }
+    }
+}
+
+fn collect_power_up_system(
+    mut commands: Commands,
+    mut player: Query<(&Transform, &mut PlayerShip)>,
+    power_ups: Query<(Entity, &Transform, &PowerUp)>,
+    mut sound_events: EventWriter<SoundEvent>,
+) {
+    if let Some((player_tr, mut ship)) = player.iter_mut().next() {
+        for (entity, tr, power_up) in power_ups.iter() {
+            if player_tr.translation.distance(tr.translation) >= 20.0 {
+                continue;
+            }
+            match power_up.kind {
+                PowerUpKind::WeaponUpgrade => ship.upgrade_frames = UPGRADE_FRAMES,
+                PowerUpKind::Shield => ship.shield_frames = SHIELD_FRAMES,
+                PowerUpKind::ExtraLife => ship.lives += 1,
+                PowerUpKind::Bomb => ship.bombs += 1,
+            }
+            commands.entity(entity).despawn();
+            sound_events.send(SoundEvent(Sound::Pickup));
+        }
+    }
+}
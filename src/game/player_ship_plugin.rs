@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+
+use crate::components::{
+    AppState, AttractMode, DevOptions, PlayerHitEvent, PlayerShip, TimeScale, Weapon,
+};
+use crate::console_plugin::{ConsoleAppExt, ConsoleCommandEvent, ConsoleCommands, ConsoleLog};
+use crate::loading_plugin::GameAssets;
+use crate::settings_plugin::{ControlScheme, Settings};
+use crate::profiler_plugin::timed;
+
+// Plugin
+pub struct PlayerShipPlugin;
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+) {
+    commands
+        .spawn()
+        .insert_bundle(SpriteBundle {
+            material: assets.player_material.clone(),
+            transform: Transform::identity(),
+            sprite: Sprite::new(Vec2::new(25.0, 25.0)),
+            ..Default::default()
+        })
+        .insert(PlayerShip::default())
+        .insert(Weapon::default());
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<PlayerShip>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+//
+fn follow_cursor_system(
+    mut query: Query<&mut PlayerShip>,
+    mut events: EventReader<CursorMoved>,
+    windows: Res<Windows>,
+    attract: Res<AttractMode>,
+    settings: Res<Settings>,
+) {
+    //the demo flies the ship
+    if attract.active || settings.scheme != ControlScheme::Mouse {
+        return;
+    }
+    //Use unwrap because the app always has a window.
+    let window = windows.iter().next().unwrap();
+    //But events don't happen every frame. We need to check whether the value exists or not.
+    if let Some(value) = events.iter().last() {
+        for mut player in query.iter_mut() {
+            player.target_x = value.position.x - window.width() / 2.0;
+            player.target_y = value.position.y - window.height() / 2.0;
+        }
+    }
+}
+
+//with the keyboard scheme, the target is kept a little ahead of the ship
+fn keyboard_control_system(
+    mut query: Query<(&Transform, &mut PlayerShip)>,
+    keys: Res<Input<KeyCode>>,
+    attract: Res<AttractMode>,
+    settings: Res<Settings>,
+) {
+    if attract.active || settings.scheme != ControlScheme::Keyboard {
+        return;
+    }
+    let bindings = &settings.keys;
+    let mut dir = Vec2::ZERO;
+    if keys.pressed(bindings.up) {
+        dir.y += 1.;
+    }
+    if keys.pressed(bindings.down) {
+        dir.y -= 1.;
+    }
+    if keys.pressed(bindings.left) {
+        dir.x -= 1.;
+    }
+    if keys.pressed(bindings.right) {
+        dir.x += 1.;
+    }
+    for (tr, mut player) in query.iter_mut() {
+        let target = Vec2::new(tr.translation.x, tr.translation.y) + dir * 10.;
+        player.target_x = target.x;
+        player.target_y = target.y;
+    }
+}
+
+fn move_player_system(
+    mut query: Query<(&mut Transform, &mut PlayerShip)>,
+    time: Res<TimeScale>,
+) {
+    for (mut tr, mut player) in query.iter_mut() {
+        let current = Vec2::new(tr.translation.x, tr.translation.y);
+        let target = Vec2::new(player.target_x, player.target_y);
+        let diff = target - current;
+        player.moving = diff.length() >= 1.0;
+        if player.moving {
+            //move
+            let mv = diff.normalize() * 1.5 * time.delta;
+            tr.translation.x += mv.x;
+            tr.translation.y += mv.y;
+            //rotate
+            let y_axis = Vec2::new(0.0, 1.0);
+            let angle = y_axis.angle_between(diff);
+            tr.rotation = Quat::from_rotation_z(angle);
+        }
+    }
+}
+
+//frames the ship can't be hit again after a hit
+const INVINCIBLE_FRAMES: i32 = 120;
+//real frames of slow motion when a life is lost
+const SLOW_MOTION_FRAMES: i32 = 45;
+
+fn player_hit_system(
+    mut query: Query<&mut PlayerShip>,
+    mut events: EventReader<PlayerHitEvent>,
+    options: Res<DevOptions>,
+    mut time: ResMut<TimeScale>,
+) {
+    //several hits can arrive in one frame, only the first one counts
+    if events.iter().next().is_none() || options.god {
+        return;
+    }
+    for mut player in query.iter_mut() {
+        //shield (from power-up or a recent hit) protects the ship
+        if player.shield_frames > 0 {
+            continue;
+        }
+        player.lives -= 1;
+        player.shield_frames = INVINCIBLE_FRAMES;
+        time.slow_motion_frames = SLOW_MOTION_FRAMES;
+    }
+}
+
+//count down timed effects, from power-ups and from hits
+fn player_effects_system(
+    mut player: Query<&mut PlayerShip>,
+    time: Res<TimeScale>,
+) {
+    for mut ship in player.iter_mut() {
+        ship.shield_frames = (ship.shield_frames - time.ticks).max(0);
+        ship.upgrade_frames = (ship.upgrade_frames - time.ticks).max(0);
+    }
+}
+
+fn console_command_system(
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    console_commands: Res<ConsoleCommands>,
+    mut options: ResMut<DevOptions>,
+) {
+    for event in events.iter() {
+        if event.command != "god" {
+            continue;
+        }
+        options.god = match event.args.get(0).map(|arg| arg.as_str()) {
+            None => !options.god,
+            Some("on") => true,
+            Some("off") => false,
+            Some(_) => {
+                log.usage(&console_commands, event);
+                continue;
+            }
+        };
+        log.print(if options.god { "god mode on" } else { "god mode off" });
+    }
+}
+
+impl Plugin for PlayerShipPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_console_command("god", "god [on|off]")
+            .add_system(console_command_system.system())
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(timed(follow_cursor_system.system()))
+                    .with_system(timed(keyboard_control_system.system()))
+                    .with_system(timed(move_player_system.system()))
+                    .with_system(timed(player_hit_system.system()))
+                    .with_system(timed(player_effects_system.system())),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup.system()));
+    }
+
+    fn name(&self) -> &str {
+        "PlayerShip"
+    }
+}
\ No newline at end of file
@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use bevy::ecs::component::Component;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::components::{AppState, Bullet, Enemy, EnemyBullet, EnemyKilledEvent, Particle, Shockwave};
+
+// Plugin
+pub struct PoolPlugin;
+
+impl Plugin for PoolPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Pool::<Bullet>::default())
+            .insert_resource(Pool::<EnemyBullet>::default())
+            .insert_resource(Pool::<Enemy>::default())
+            .insert_resource(Pool::<Particle>::default())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(log_pool_metrics_system.system()),
+            )
+            //after every kill of the frame, before a reused enemy can be hit again
+            .add_system_to_stage(CoreStage::PostUpdate, forget_killed_enemies_system.system())
+            //leaving the game (restart, quit to title) puts everything back
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame)
+                    .with_system(release_all_system::<Bullet>.system())
+                    .with_system(release_all_system::<EnemyBullet>.system())
+                    .with_system(release_all_system::<Enemy>.system())
+                    .with_system(release_all_system::<Particle>.system()),
+            );
+    }
+    fn name(&self) -> &str {
+        "PoolPlugin"
+    }
+}
+
+//Recycles entities instead of despawning them, used as Resource.
+//Released entities are hidden, so systems skip entities whose Visible is false.
+//T is only used to have one pool per kind of entity.
+pub struct Pool<T> {
+    free: Vec<Entity>,
+    live: HashSet<Entity>,
+    owned: HashSet<Entity>,
+    //metrics
+    pub created: usize,
+    pub reused: usize,
+    pub peak_live: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Pool {
+            free: Vec::new(),
+            live: HashSet::new(),
+            owned: HashSet::new(),
+            created: 0,
+            reused: 0,
+            peak_live: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Pool<T> {
+    pub fn live_count(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn free_count(&self) -> usize {
+        self.free.len()
+    }
+
+    //register an entity spawned in advance, it starts hidden and free
+    pub fn add_free(&mut self, entity: Entity) {
+        self.owned.insert(entity);
+        self.free.push(entity);
+        self.created += 1;
+    }
+
+    //take a free entity, None when the pool is empty
+    pub fn acquire(&mut self) -> Option<Entity> {
+        let entity = self.free.pop()?;
+        self.reused += 1;
+        self.mark_live(entity);
+        Some(entity)
+    }
+
+    //reuse a free entity or spawn a new one.
+    //Insert the same components as a fresh spawn so the entity stays in its archetype.
+    pub fn spawn<'a, 'b>(&mut self, commands: &'b mut Commands<'a>) -> EntityCommands<'a, 'b> {
+        let entity = match self.acquire() {
+            Some(entity) => entity,
+            None => {
+                let entity = commands.spawn().id();
+                self.owned.insert(entity);
+                self.created += 1;
+                self.mark_live(entity);
+                entity
+            }
+        };
+        commands.entity(entity)
+    }
+
+    //hide a live entity and put it back.
+    //Returns false if the entity doesn't belong to this pool, the caller should despawn it.
+    pub fn release(&mut self, entity: Entity, visible: &mut Visible) -> bool {
+        if self.live.remove(&entity) {
+            visible.is_visible = false;
+            self.free.push(entity);
+            return true;
+        }
+        //already released in this frame
+        self.owned.contains(&entity)
+    }
+
+    //true if the entity is ours, whether live or free
+    pub fn owns(&self, entity: Entity) -> bool {
+        self.owned.contains(&entity)
+    }
+
+    fn mark_live(&mut self, entity: Entity) {
+        self.live.insert(entity);
+        self.peak_live = self.peak_live.max(self.live.len());
+    }
+}
+
+//release every entity with a T component, despawning the ones the pool doesn't own
+fn release_all_system<T: Component>(
+    mut commands: Commands,
+    mut pool: ResMut<Pool<T>>,
+    mut query: Query<(Entity, &mut Visible), With<T>>,
+) {
+    for (entity, mut visible) in query.iter_mut() {
+        if !pool.release(entity, &mut visible) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+//A killed enemy comes back from the pool as the same Entity.
+//Piercing bullets and shockwaves still in flight would pass through it, so they forget it.
+fn forget_killed_enemies_system(
+    mut events: EventReader<EnemyKilledEvent>,
+    mut bullets: Query<&mut Bullet>,
+    mut shockwaves: Query<&mut Shockwave>,
+) {
+    for event in events.iter() {
+        for mut bullet in bullets.iter_mut() {
+            bullet.hit_enemies.retain(|entity| *entity != event.entity);
+        }
+        for mut wave in shockwaves.iter_mut() {
+            wave.hit_enemies.retain(|entity| *entity != event.entity);
+        }
+    }
+}
+
+fn log_metrics<T>(name: &str, pool: &Pool<T>) {
+    info!(
+        "pool {}: live {}, free {}, peak {}, created {}, reused {}",
+        name,
+        pool.live_count(),
+        pool.free_count(),
+        pool.peak_live,
+        pool.created,
+        pool.reused
+    );
+}
+
+//print pool sizes every 10 seconds
+fn log_pool_metrics_system(
+    mut frames: Local<i32>,
+    bullets: Res<Pool<Bullet>>,
+    enemy_bullets: Res<Pool<EnemyBullet>>,
+    enemies: Res<Pool<Enemy>>,
+    particles: Res<Pool<Particle>>,
+) {
+    *frames += 1;
+    if *frames < 600 {
+        return;
+    }
+    *frames = 0;
+    log_metrics("bullets", &bullets);
+    log_metrics("enemy_bullets", &enemy_bullets);
+    log_metrics("enemies", &enemies);
+    log_metrics("particles", &particles);
+}
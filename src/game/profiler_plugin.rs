@@ -0,0 +1,494 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use bevy::ecs::archetype::{Archetype, ArchetypeComponentId};
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::query::Access;
+use bevy::ecs::system::{System, SystemId};
+use bevy::prelude::*;
+
+use crate::console_plugin::{ConsoleAppExt, ConsoleCommandEvent, ConsoleCommands, ConsoleLog};
+use crate::loading_plugin::GameAssets;
+use crate::storage::data_file;
+
+// Plugin
+//Times the gameplay systems wrapped with timed().
+//F4 shows the slowest systems with a histogram of their run times and a graph of the
+//gameplay time per frame. The console command profile exports the recorded frames.
+pub struct ProfilerPlugin;
+
+impl Plugin for ProfilerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let profiler = app
+            .world_mut()
+            .get_resource_or_insert_with(Profiler::default);
+        profiler.shared.recording.store(true, Ordering::Relaxed);
+        app.add_console_command("profile", "profile [csv|trace]")
+            .add_startup_system(setup.system())
+            .add_system(console_command_system.system())
+            .add_system(toggle_overlay_system.system())
+            .add_system(profiler_text_system.system())
+            .add_system(profiler_graph_system.system())
+            //after every timed system of the frame has run
+            .add_system_to_stage(CoreStage::Last, collect_timings_system.system());
+    }
+    fn name(&self) -> &str {
+        "ProfilerPlugin"
+    }
+}
+
+//frames kept for the overlay and the export
+const HISTORY_FRAMES: usize = 600;
+//bars of the frame time graph, the latest frames
+const GRAPH_BARS: usize = 120;
+const GRAPH_HEIGHT: f32 = 80.0;
+//histogram buckets are powers of two in microseconds, the last one is 2048us and more
+const BUCKETS: usize = 12;
+//systems listed in the overlay
+const LISTED_SYSTEMS: usize = 10;
+
+//One run of a timed system
+#[derive(Clone, Copy)]
+pub struct Sample {
+    pub system: usize,
+    pub start: Instant,
+    pub duration: Duration,
+    pub thread: ThreadId,
+}
+
+//Filled by timed systems from any thread
+#[derive(Default)]
+struct ProfilerShared {
+    //nothing is recorded without ProfilerPlugin
+    recording: AtomicBool,
+    names: Mutex<Vec<Cow<'static, str>>>,
+    samples: Mutex<Vec<Sample>>,
+}
+
+//Run times of the timed systems, used as Resource
+pub struct Profiler {
+    shared: Arc<ProfilerShared>,
+    //time origin of the export
+    start: Instant,
+    frame: u64,
+    //the last HISTORY_FRAMES frames with their samples
+    frames: VecDeque<(u64, Vec<Sample>)>,
+    pub overlay: bool,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler {
+            shared: Default::default(),
+            start: Instant::now(),
+            frame: 0,
+            frames: VecDeque::new(),
+            overlay: false,
+        }
+    }
+}
+
+//Summary of one system over the recorded frames
+pub struct SystemStats {
+    pub name: String,
+    pub average: Duration,
+    pub max: Duration,
+    pub histogram: [u32; BUCKETS],
+}
+
+impl Profiler {
+    //the samples of the frame that just ended
+    fn collect(&mut self) {
+        let samples = std::mem::take(&mut *self.shared.samples.lock().unwrap());
+        self.frame += 1;
+        self.frames.push_back((self.frame, samples));
+        while self.frames.len() > HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+    }
+
+    //without the module path
+    pub fn system_name(&self, system: usize) -> String {
+        let names = self.shared.names.lock().unwrap();
+        let name = names.get(system).map(|name| name.as_ref()).unwrap_or("?");
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
+
+    //time of the timed systems in each recorded frame, they may run in parallel
+    pub fn frame_totals(&self) -> Vec<Duration> {
+        self.frames
+            .iter()
+            .map(|(_, samples)| samples.iter().map(|sample| sample.duration).sum())
+            .collect()
+    }
+
+    //slowest on average first
+    pub fn stats(&self) -> Vec<SystemStats> {
+        let mut durations: HashMap<usize, Vec<Duration>> = HashMap::new();
+        for (_, samples) in self.frames.iter() {
+            for sample in samples {
+                durations.entry(sample.system).or_default().push(sample.duration);
+            }
+        }
+        let mut stats = durations
+            .into_iter()
+            .map(|(system, durations)| {
+                let mut histogram = [0; BUCKETS];
+                for duration in durations.iter() {
+                    histogram[bucket(*duration)] += 1;
+                }
+                SystemStats {
+                    name: self.system_name(system),
+                    average: durations.iter().sum::<Duration>() / durations.len() as u32,
+                    max: durations.iter().max().cloned().unwrap_or_default(),
+                    histogram,
+                }
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| b.average.cmp(&a.average));
+        stats
+    }
+
+    //frame,system,start_us,duration_us
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "frame,system,start_us,duration_us")?;
+        for (frame, samples) in self.frames.iter() {
+            for sample in samples {
+                writeln!(
+                    out,
+                    "{},{},{},{}",
+                    frame,
+                    self.system_name(sample.system),
+                    self.micros_since_start(sample.start),
+                    sample.duration.as_micros()
+                )?;
+            }
+        }
+        out.flush()
+    }
+
+    //the Trace Event Format, opened with chrome://tracing or Perfetto
+    pub fn write_chrome_trace(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let mut threads: HashMap<ThreadId, usize> = HashMap::new();
+        writeln!(out, "{{\"traceEvents\":[")?;
+        let mut first = true;
+        for (frame, samples) in self.frames.iter() {
+            for sample in samples {
+                let next_thread = threads.len();
+                let thread = *threads.entry(sample.thread).or_insert(next_thread);
+                if !first {
+                    writeln!(out, ",")?;
+                }
+                first = false;
+                write!(
+                    out,
+                    "{{\"name\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":1,\"tid\":{},\"args\":{{\"frame\":{}}}}}",
+                    self.system_name(sample.system),
+                    self.micros_since_start(sample.start),
+                    sample.duration.as_micros(),
+                    thread,
+                    frame
+                )?;
+            }
+        }
+        writeln!(out, "\n]}}")?;
+        out.flush()
+    }
+
+    fn micros_since_start(&self, instant: Instant) -> u128 {
+        instant.saturating_duration_since(self.start).as_micros()
+    }
+}
+
+//histogram bucket of a run time
+fn bucket(duration: Duration) -> usize {
+    let micros = duration.as_micros().max(1) as u64;
+    (63 - micros.leading_zeros() as usize).min(BUCKETS - 1)
+}
+
+//A system whose run time is recorded in Profiler
+pub struct Timed<S> {
+    system: S,
+    shared: Option<Arc<ProfilerShared>>,
+    index: usize,
+}
+
+//wrap a gameplay system to time it, like .with_system(timed(move_enemy_system.system()))
+pub fn timed<S: System<In = (), Out = ()>>(system: S) -> Timed<S> {
+    Timed {
+        system,
+        shared: None,
+        index: 0,
+    }
+}
+
+impl<S: System<In = (), Out = ()>> System for Timed<S> {
+    type In = ();
+    type Out = ();
+
+    fn name(&self) -> Cow<'static, str> {
+        self.system.name()
+    }
+
+    fn id(&self) -> SystemId {
+        self.system.id()
+    }
+
+    fn new_archetype(&mut self, archetype: &Archetype) {
+        self.system.new_archetype(archetype);
+    }
+
+    fn component_access(&self) -> &Access<ComponentId> {
+        self.system.component_access()
+    }
+
+    fn archetype_component_access(&self) -> &Access<ArchetypeComponentId> {
+        self.system.archetype_component_access()
+    }
+
+    fn is_send(&self) -> bool {
+        self.system.is_send()
+    }
+
+    unsafe fn run_unsafe(&mut self, input: (), world: &World) {
+        let shared = match &self.shared {
+            Some(shared) if shared.recording.load(Ordering::Relaxed) => shared,
+            _ => return self.system.run_unsafe(input, world),
+        };
+        let start = Instant::now();
+        self.system.run_unsafe(input, world);
+        let sample = Sample {
+            system: self.index,
+            start,
+            duration: start.elapsed(),
+            thread: thread::current().id(),
+        };
+        shared.samples.lock().unwrap().push(sample);
+    }
+
+    fn apply_buffers(&mut self, world: &mut World) {
+        self.system.apply_buffers(world);
+    }
+
+    fn initialize(&mut self, world: &mut World) {
+        self.system.initialize(world);
+        let profiler = world.get_resource_or_insert_with(Profiler::default);
+        let mut names = profiler.shared.names.lock().unwrap();
+        self.index = names.len();
+        names.push(self.system.name());
+        self.shared = Some(profiler.shared.clone());
+    }
+
+    fn check_change_tick(&mut self, change_tick: u32) {
+        self.system.check_change_tick(change_tick);
+    }
+}
+
+//Marker for the text of the overlay
+struct ProfilerText;
+
+//Marker for the graph of the overlay
+struct ProfilerGraph;
+
+//One bar of the graph, 0 is the oldest frame
+struct ProfilerBar {
+    index: usize,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    assets: Res<GameAssets>,
+) {
+    let bar_material = materials.add(Color::rgb(0.2, 0.9, 0.4).into());
+    commands
+        .spawn()
+        .insert_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(GRAPH_HEIGHT + 12.0),
+                    left: Val::Px(6.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 11.0,
+                    color: Color::rgb(0.2, 0.9, 0.4),
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(ProfilerText);
+    commands
+        .spawn()
+        .insert_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(GRAPH_BARS as f32 * 2.0), Val::Px(GRAPH_HEIGHT)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(6.0),
+                    left: Val::Px(6.0),
+                    ..Default::default()
+                },
+                align_items: AlignItems::FlexStart,
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.5).into()),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(ProfilerGraph)
+        .with_children(|parent| {
+            for index in 0..GRAPH_BARS {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(2.0), Val::Px(0.0)),
+                            ..Default::default()
+                        },
+                        material: bar_material.clone(),
+                        visible: Visible {
+                            is_visible: false,
+                            is_transparent: true,
+                        },
+                        ..Default::default()
+                    })
+                    .insert(ProfilerBar { index });
+            }
+        });
+}
+
+fn collect_timings_system(
+    mut profiler: ResMut<Profiler>,
+) {
+    profiler.collect();
+}
+
+fn toggle_overlay_system(
+    keys: Res<Input<KeyCode>>,
+    mut profiler: ResMut<Profiler>,
+) {
+    if keys.just_pressed(KeyCode::F4) {
+        profiler.overlay = !profiler.overlay;
+    }
+}
+
+fn profiler_text_system(
+    profiler: Res<Profiler>,
+    mut texts: Query<&mut Text, With<ProfilerText>>,
+) {
+    let mut message = String::new();
+    if profiler.overlay {
+        let blocks = ['.', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        message.push_str("system                       avg us  max us  histogram 1us..2ms\n");
+        for stats in profiler.stats().iter().take(LISTED_SYSTEMS) {
+            let most = *stats.histogram.iter().max().unwrap_or(&1);
+            let histogram = stats
+                .histogram
+                .iter()
+                .map(|count| match *count {
+                    0 => blocks[0],
+                    count => blocks[1 + (count * 7 / most.max(1)) as usize],
+                })
+                .collect::<String>();
+            message.push_str(&format!(
+                "{:<28} {:>6} {:>7}  {}\n",
+                stats.name,
+                stats.average.as_micros(),
+                stats.max.as_micros(),
+                histogram
+            ));
+        }
+    }
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
+
+//one bar per frame, scaled to the slowest frame shown
+fn profiler_graph_system(
+    profiler: Res<Profiler>,
+    mut graphs: Query<&mut Visible, (With<ProfilerGraph>, Without<ProfilerBar>)>,
+    mut bars: Query<(&ProfilerBar, &mut Style, &mut Visible)>,
+) {
+    for mut visible in graphs.iter_mut() {
+        visible.is_visible = profiler.overlay;
+    }
+    if !profiler.overlay {
+        for (_, _, mut visible) in bars.iter_mut() {
+            visible.is_visible = false;
+        }
+        return;
+    }
+    let totals = profiler.frame_totals();
+    let shown = &totals[totals.len().saturating_sub(GRAPH_BARS)..];
+    let slowest = shown.iter().max().cloned().unwrap_or_default().as_secs_f32();
+    for (bar, mut style, mut visible) in bars.iter_mut() {
+        visible.is_visible = true;
+        let height = match shown.get(bar.index) {
+            Some(total) if slowest > 0.0 => total.as_secs_f32() / slowest * GRAPH_HEIGHT,
+            _ => 0.0,
+        };
+        style.size.height = Val::Px(height);
+    }
+}
+
+fn console_command_system(
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    console_commands: Res<ConsoleCommands>,
+    mut profiler: ResMut<Profiler>,
+) {
+    for event in events.iter() {
+        if event.command != "profile" {
+            continue;
+        }
+        let (file, trace) = match event.args.get(0).map(|arg| arg.as_str()) {
+            None => {
+                profiler.overlay = !profiler.overlay;
+                continue;
+            }
+            Some("csv") => ("profile.csv", false),
+            Some("trace") => ("profile.json", true),
+            Some(_) => {
+                log.usage(&console_commands, event);
+                continue;
+            }
+        };
+        let path = match data_file(file) {
+            Some(path) => path,
+            None => continue,
+        };
+        let result = if trace {
+            profiler.write_chrome_trace(&path)
+        } else {
+            profiler.write_csv(&path)
+        };
+        match result {
+            Ok(()) => log.print(format!(
+                "{} frames written to {}",
+                profiler.frames.len(),
+                path.display()
+            )),
+            Err(e) => log.print(format!("can't write {}: {}", path.display(), e)),
+        }
+    }
+}
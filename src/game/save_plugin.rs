@@ -0,0 +1,980 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use bevy::app::Events;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::components::{
+    AppState, Arena, Boss, BossPart, Bullet, Difficulty, Enemy, EnemyBullet, FirePattern,
+    GameRng, GlobalEnemyState, PlayerShip, PowerUp, PowerUpKind, Score, Shockwave, Shooter,
+    Weapon, WeaponKind,
+};
+use crate::enemy_plugin::spawn_boss;
+use crate::loading_plugin::{GameAssets, POWER_UPS, WEAPONS};
+use crate::pool::Pool;
+use crate::storage::{backup_broken_file, data_file, read_file, remove_file, write_file};
+
+// Plugin
+//Saves the run when the player leaves it from the pause menu, and restores it from the title screen.
+//Restoring happens in LoadingRun, pushed on top of InGame after the usual InGame setup,
+//so the saved state replaces the fresh one before any gameplay system runs.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.world_mut().contains_resource::<Events<SaveRunEvent>>() {
+            app.add_event::<SaveRunEvent>();
+        }
+        app.init_resource::<PendingRun>()
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused).with_system(save_run_system.system()),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame).with_system(begin_restore_system.system()),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::LoadingRun).with_system(restore_run_system.system()),
+            );
+    }
+    fn name(&self) -> &str {
+        "SavePlugin"
+    }
+}
+
+//Sent by the pause menu before leaving the run
+pub struct SaveRunEvent;
+
+//A run loaded from the save file, waiting for InGame, used as Resource
+#[derive(Default)]
+pub struct PendingRun(pub Option<Vec<Record>>);
+
+const FILE_NAME: &str = "run.sav";
+//first line of the file, the number is increased when the format changes
+const FILE_HEADER: &str = "run";
+const FILE_VERSION: u32 = 4;
+
+//Shooter as read from the file
+pub struct SavedShooter {
+    enabled: bool,
+    pattern: FirePattern,
+    interval: i32,
+    frames_to_fire: i32,
+    bullet_speed: f32,
+    angle: f32,
+}
+
+impl SavedShooter {
+    fn to_shooter(&self) -> Shooter {
+        Shooter {
+            enabled: self.enabled,
+            pattern: self.pattern,
+            interval: self.interval,
+            frames_to_fire: self.frames_to_fire,
+            bullet_speed: self.bullet_speed,
+            angle: self.angle,
+        }
+    }
+}
+
+//One line of the save file.
+//Enemies keep the id of their entity, so the bullets and shockwaves that hit them still know them.
+pub enum Record {
+    Run {
+        seed: u64,
+        rng: u64,
+        points: u32,
+        wave: u32,
+    },
+    Waves {
+        frames_to_next_enemy: i32,
+        spawn_interval: i32,
+        wave: usize,
+        spawned_in_wave: i32,
+        boss_spawned: bool,
+    },
+    Player {
+        transform: Transform,
+        target: Vec2,
+        lives: i32,
+        bombs: i32,
+        shield_frames: i32,
+        upgrade_frames: i32,
+        weapon: WeaponKind,
+        frames_to_fire: i32,
+    },
+    Enemy {
+        id: u32,
+        transform: Transform,
+        frames_to_appear: i32,
+        health: i32,
+        velocity: Vec2,
+        shooter: SavedShooter,
+    },
+    //position comes from Boss::frames, bosses and their parts don't steer so have no velocity
+    Boss {
+        id: u32,
+        frames_to_appear: i32,
+        health: i32,
+        max_health: i32,
+        phase: usize,
+        frames: i32,
+        shooter: SavedShooter,
+    },
+    BossPart {
+        id: u32,
+        offset_x: f32,
+        frames_to_appear: i32,
+        health: i32,
+        shooter: SavedShooter,
+    },
+    Bullet {
+        transform: Transform,
+        kind: WeaponKind,
+        velocity: Vec2,
+        damage: i32,
+        piercing: bool,
+        homing: bool,
+        bounces: i32,
+        hit_enemy_ids: Vec<u32>,
+    },
+    EnemyBullet {
+        translation: Vec3,
+        velocity: Vec2,
+    },
+    PowerUp {
+        translation: Vec3,
+        kind: PowerUpKind,
+        velocity: Vec2,
+        frames_to_expire: i32,
+    },
+    Shockwave {
+        translation: Vec3,
+        radius: f32,
+        max_radius: f32,
+        speed: f32,
+        damage: i32,
+        hit_enemy_ids: Vec<u32>,
+    },
+}
+
+fn save_path() -> Option<PathBuf> {
+    data_file(FILE_NAME)
+}
+
+pub fn saved_run_exists() -> bool {
+    save_path().map_or(false, |path| path.exists())
+}
+
+//read and remove the save, a run can be resumed once.
+//None if there is no save or it's broken (kept as .bak).
+pub fn take_saved_run() -> Option<Vec<Record>> {
+    let path = save_path()?;
+    let text = read_file(&path)?;
+    match parse(&text) {
+        Some(records) => {
+            remove_file(&path);
+            Some(records)
+        }
+        None => {
+            backup_broken_file(&path);
+            None
+        }
+    }
+}
+
+// Writing
+//floats are written with Display, which reads back to the same value
+
+fn write_transform(line: &mut String, tr: &Transform) {
+    let t = tr.translation;
+    let r = tr.rotation;
+    line.push_str(&format!(" {} {} {} {} {} {} {}", t.x, t.y, t.z, r.x, r.y, r.z, r.w));
+}
+
+fn write_shooter(line: &mut String, shooter: &Shooter) {
+    let pattern = match shooter.pattern {
+        FirePattern::Aimed => "Aimed".to_string(),
+        FirePattern::Spiral => "Spiral".to_string(),
+        FirePattern::RadialBurst(count) => format!("RadialBurst{}", count),
+    };
+    line.push_str(&format!(
+        " {} {} {} {} {} {}",
+        shooter.enabled,
+        pattern,
+        shooter.interval,
+        shooter.frames_to_fire,
+        shooter.bullet_speed,
+        shooter.angle
+    ));
+}
+
+//a count, then the ids of the entities
+fn write_ids(line: &mut String, entities: &[Entity]) {
+    line.push_str(&format!(" {}", entities.len()));
+    for entity in entities.iter() {
+        line.push_str(&format!(" {}", entity.id()));
+    }
+}
+
+//Everything a run is saved from
+#[derive(SystemParam)]
+pub struct RunSnapshot<'a> {
+    player: Query<'a, (&'static Transform, &'static PlayerShip, &'static Weapon)>,
+    enemies: Query<
+        'a,
+        (Entity, &'static Transform, &'static Enemy, &'static Shooter, &'static Visible),
+        (Without<Boss>, Without<BossPart>),
+    >,
+    bosses: Query<'a, (Entity, &'static Enemy, &'static Boss, &'static Shooter)>,
+    parts: Query<'a, (Entity, &'static Enemy, &'static BossPart, &'static Shooter)>,
+    bullets: Query<'a, (&'static Transform, &'static Bullet, &'static Visible)>,
+    enemy_bullets: Query<'a, (&'static Transform, &'static EnemyBullet, &'static Visible)>,
+    power_ups: Query<'a, (&'static Transform, &'static PowerUp)>,
+    shockwaves: Query<'a, (&'static Transform, &'static Shockwave)>,
+    score: Res<'a, Score>,
+    rng: Res<'a, GameRng>,
+    enemy_state: Res<'a, GlobalEnemyState>,
+}
+
+fn save_run_system(
+    mut events: EventReader<SaveRunEvent>,
+    run: RunSnapshot,
+) {
+    if events.iter().next().is_none() {
+        return;
+    }
+    let path = match save_path() {
+        Some(path) => path,
+        None => return,
+    };
+    write_file(&path, &run.to_text());
+    info!("run saved to {}", path.display());
+}
+
+impl<'a> RunSnapshot<'a> {
+    //the whole save file
+    fn to_text(&self) -> String {
+        let score = &self.score;
+        let rng = &self.rng;
+        let enemy_state = &self.enemy_state;
+        let mut lines = vec![
+            format!("{} {}", FILE_HEADER, FILE_VERSION),
+            format!("run {} {} {} {}", score.seed, rng.state, score.points, score.wave),
+            format!(
+                "waves {} {} {} {} {}",
+                enemy_state.frames_to_next_enemy,
+                enemy_state.spawn_interval,
+                enemy_state.wave,
+                enemy_state.spawned_in_wave,
+                enemy_state.boss_spawned
+            ),
+        ];
+        for (tr, ship, weapon) in self.player.iter() {
+            let mut line = String::from("player");
+            write_transform(&mut line, tr);
+            line.push_str(&format!(
+                " {} {} {} {} {} {} {:?} {}",
+                ship.target_x,
+                ship.target_y,
+                ship.lives,
+                ship.bombs,
+                ship.shield_frames,
+                ship.upgrade_frames,
+                weapon.kind,
+                weapon.frames_to_fire
+            ));
+            lines.push(line);
+        }
+        //hidden ones are free in their pool
+        let enemies = self.enemies.iter().filter(|(_, _, _, _, v)| v.is_visible);
+        for (entity, tr, enemy, shooter, _) in enemies {
+            let mut line = format!("enemy {}", entity.id());
+            write_transform(&mut line, tr);
+            line.push_str(&format!(
+                " {} {} {} {}",
+                enemy.frames_to_appear, enemy.health, enemy.velocity.x, enemy.velocity.y
+            ));
+            write_shooter(&mut line, shooter);
+            lines.push(line);
+        }
+        for (entity, enemy, boss, shooter) in self.bosses.iter() {
+            let mut line = format!(
+                "boss {} {} {} {} {} {}",
+                entity.id(),
+                enemy.frames_to_appear,
+                enemy.health,
+                boss.max_health,
+                boss.phase,
+                boss.frames
+            );
+            write_shooter(&mut line, shooter);
+            lines.push(line);
+        }
+        for (entity, enemy, part, shooter) in self.parts.iter() {
+            let mut line = format!(
+                "boss_part {} {} {} {}",
+                entity.id(),
+                part.offset.x,
+                enemy.frames_to_appear,
+                enemy.health
+            );
+            write_shooter(&mut line, shooter);
+            lines.push(line);
+        }
+        for (tr, bullet, _) in self.bullets.iter().filter(|(_, _, v)| v.is_visible) {
+            let mut line = String::from("bullet");
+            write_transform(&mut line, tr);
+            line.push_str(&format!(
+                " {:?} {} {} {} {} {} {}",
+                bullet.kind,
+                bullet.velocity.x,
+                bullet.velocity.y,
+                bullet.damage,
+                bullet.piercing,
+                bullet.homing,
+                bullet.bounces
+            ));
+            write_ids(&mut line, &bullet.hit_enemies);
+            lines.push(line);
+        }
+        for (tr, bullet, _) in self.enemy_bullets.iter().filter(|(_, _, v)| v.is_visible) {
+            let t = tr.translation;
+            lines.push(format!(
+                "enemy_bullet {} {} {} {} {}",
+                t.x, t.y, t.z, bullet.velocity.x, bullet.velocity.y
+            ));
+        }
+        for (tr, power_up) in self.power_ups.iter() {
+            let t = tr.translation;
+            lines.push(format!(
+                "power_up {} {} {} {:?} {} {} {}",
+                t.x,
+                t.y,
+                t.z,
+                power_up.kind,
+                power_up.velocity.x,
+                power_up.velocity.y,
+                power_up.frames_to_expire
+            ));
+        }
+        for (tr, wave) in self.shockwaves.iter() {
+            let t = tr.translation;
+            let mut line = format!(
+                "shockwave {} {} {} {} {} {} {}",
+                t.x, t.y, t.z, wave.radius, wave.max_radius, wave.speed, wave.damage
+            );
+            write_ids(&mut line, &wave.hit_enemies);
+            lines.push(line);
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+// Reading
+
+//The values of one line, read in order
+struct Fields<'a> {
+    iter: std::str::SplitWhitespace<'a>,
+}
+
+impl<'a> Fields<'a> {
+    fn next<T: FromStr>(&mut self) -> Option<T> {
+        self.iter.next()?.parse().ok()
+    }
+
+    fn next_bool(&mut self) -> Option<bool> {
+        match self.iter.next()? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn next_vec2(&mut self) -> Option<Vec2> {
+        Some(Vec2::new(self.next()?, self.next()?))
+    }
+
+    fn next_vec3(&mut self) -> Option<Vec3> {
+        Some(Vec3::new(self.next()?, self.next()?, self.next()?))
+    }
+
+    fn next_transform(&mut self) -> Option<Transform> {
+        let translation = self.next_vec3()?;
+        let rotation = Quat::from_xyzw(self.next()?, self.next()?, self.next()?, self.next()?);
+        Some(Transform {
+            translation,
+            rotation,
+            ..Default::default()
+        })
+    }
+
+    fn next_weapon(&mut self) -> Option<WeaponKind> {
+        let name = self.iter.next()?;
+        WEAPONS.iter().find(|kind| format!("{:?}", kind) == name).cloned()
+    }
+
+    fn next_power_up(&mut self) -> Option<PowerUpKind> {
+        let name = self.iter.next()?;
+        POWER_UPS.iter().find(|kind| format!("{:?}", kind) == name).cloned()
+    }
+
+    fn next_shooter(&mut self) -> Option<SavedShooter> {
+        let enabled = self.next_bool()?;
+        let pattern = match self.iter.next()? {
+            "Aimed" => FirePattern::Aimed,
+            "Spiral" => FirePattern::Spiral,
+            name if name.starts_with("RadialBurst") => {
+                FirePattern::RadialBurst(name["RadialBurst".len()..].parse().ok()?)
+            }
+            _ => return None,
+        };
+        Some(SavedShooter {
+            enabled,
+            pattern,
+            interval: self.next()?,
+            frames_to_fire: self.next()?,
+            bullet_speed: self.next()?,
+            angle: self.next()?,
+        })
+    }
+
+    fn next_ids(&mut self) -> Option<Vec<u32>> {
+        let count: usize = self.next()?;
+        (0..count).map(|_| self.next::<u32>()).collect()
+    }
+
+    //true if every value was read
+    fn is_done(&mut self) -> bool {
+        self.iter.next().is_none()
+    }
+}
+
+fn parse_record(line: &str) -> Option<Record> {
+    let mut iter = line.split_whitespace();
+    let tag = iter.next()?;
+    let mut f = Fields { iter };
+    let record = match tag {
+        "run" => Record::Run {
+            seed: f.next()?,
+            rng: f.next()?,
+            points: f.next()?,
+            wave: f.next()?,
+        },
+        "waves" => Record::Waves {
+            frames_to_next_enemy: f.next()?,
+            spawn_interval: f.next()?,
+            wave: f.next()?,
+            spawned_in_wave: f.next()?,
+            boss_spawned: f.next_bool()?,
+        },
+        "player" => Record::Player {
+            transform: f.next_transform()?,
+            target: f.next_vec2()?,
+            lives: f.next()?,
+            bombs: f.next()?,
+            shield_frames: f.next()?,
+            upgrade_frames: f.next()?,
+            weapon: f.next_weapon()?,
+            frames_to_fire: f.next()?,
+        },
+        "enemy" => Record::Enemy {
+            id: f.next()?,
+            transform: f.next_transform()?,
+            frames_to_appear: f.next()?,
+            health: f.next()?,
+            velocity: f.next_vec2()?,
+            shooter: f.next_shooter()?,
+        },
+        "boss" => Record::Boss {
+            id: f.next()?,
+            frames_to_appear: f.next()?,
+            health: f.next()?,
+            max_health: f.next()?,
+            phase: f.next()?,
+            frames: f.next()?,
+            shooter: f.next_shooter()?,
+        },
+        "boss_part" => Record::BossPart {
+            id: f.next()?,
+            offset_x: f.next()?,
+            frames_to_appear: f.next()?,
+            health: f.next()?,
+            shooter: f.next_shooter()?,
+        },
+        "bullet" => Record::Bullet {
+            transform: f.next_transform()?,
+            kind: f.next_weapon()?,
+            velocity: f.next_vec2()?,
+            damage: f.next()?,
+            piercing: f.next_bool()?,
+            homing: f.next_bool()?,
+            bounces: f.next()?,
+            hit_enemy_ids: f.next_ids()?,
+        },
+        "enemy_bullet" => Record::EnemyBullet {
+            translation: f.next_vec3()?,
+            velocity: f.next_vec2()?,
+        },
+        "power_up" => Record::PowerUp {
+            translation: f.next_vec3()?,
+            kind: f.next_power_up()?,
+            velocity: f.next_vec2()?,
+            frames_to_expire: f.next()?,
+        },
+        "shockwave" => Record::Shockwave {
+            translation: f.next_vec3()?,
+            radius: f.next()?,
+            max_radius: f.next()?,
+            speed: f.next()?,
+            damage: f.next()?,
+            hit_enemy_ids: f.next_ids()?,
+        },
+        _ => return None,
+    };
+    if !f.is_done() {
+        return None;
+    }
+    Some(record)
+}
+
+//None if the file is broken or from another version
+fn parse(text: &str) -> Option<Vec<Record>> {
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines.next()?.split_whitespace().collect();
+    if header.len() != 2 || header[0] != FILE_HEADER || header[1].parse::<u32>().ok() != Some(FILE_VERSION) {
+        return None;
+    }
+    let mut records = Vec::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        records.push(parse_record(line)?);
+    }
+    //a run without the player can't be resumed
+    if !records.iter().any(|r| matches!(r, Record::Player { .. })) {
+        return None;
+    }
+    Some(records)
+}
+
+// Restoring
+
+fn begin_restore_system(
+    pending: Res<PendingRun>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if pending.0.is_some() {
+        state.push(AppState::LoadingRun).unwrap();
+    }
+}
+
+//the entities of a fresh InGame exist now, replace their state with the saved one
+fn restore_run_system(
+    mut commands: Commands,
+    mut pending: ResMut<PendingRun>,
+    mut player: Query<(&mut Transform, &mut PlayerShip, &mut Weapon)>,
+    mut enemy_pool: ResMut<Pool<Enemy>>,
+    mut bullet_pool: ResMut<Pool<Bullet>>,
+    mut enemy_bullet_pool: ResMut<Pool<EnemyBullet>>,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+    mut enemy_state: ResMut<GlobalEnemyState>,
+    assets: Res<GameAssets>,
+    arena: Res<Arena>,
+    difficulty: Res<Difficulty>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let records = pending.0.take().unwrap_or_default();
+    //spawned with the boss, removed again if they were destroyed before saving
+    let mut boss_parts: Vec<(Entity, f32)> = Vec::new();
+    let mut saved_parts: Vec<&Record> = Vec::new();
+    //saved id of an enemy to its new entity
+    let mut enemy_ids: HashMap<u32, Entity> = HashMap::new();
+    //bullets and shockwaves are restored after all enemies have their new ids
+    let mut hitting: Vec<&Record> = Vec::new();
+    for record in records.iter() {
+        match record {
+            Record::Run { seed, rng: rng_state, points, wave } => {
+                score.seed = *seed;
+                score.points = *points;
+                score.wave = *wave;
+                rng.state = *rng_state;
+            }
+            Record::Waves {
+                frames_to_next_enemy,
+                spawn_interval,
+                wave,
+                spawned_in_wave,
+                boss_spawned,
+            } => {
+                *enemy_state = GlobalEnemyState {
+                    frames_to_next_enemy: *frames_to_next_enemy,
+                    spawn_interval: *spawn_interval,
+                    wave: *wave,
+                    spawned_in_wave: *spawned_in_wave,
+                    boss_spawned: *boss_spawned,
+                };
+            }
+            Record::Player {
+                transform,
+                target,
+                lives,
+                bombs,
+                shield_frames,
+                upgrade_frames,
+                weapon,
+                frames_to_fire,
+            } => {
+                for (mut tr, mut ship, mut current) in player.iter_mut() {
+                    *tr = *transform;
+                    *ship = PlayerShip {
+                        target_x: target.x,
+                        target_y: target.y,
+                        moving: false,
+                        lives: *lives,
+                        bombs: *bombs,
+                        shield_frames: *shield_frames,
+                        upgrade_frames: *upgrade_frames,
+                    };
+                    *current = Weapon {
+                        kind: *weapon,
+                        frames_to_fire: *frames_to_fire,
+                    };
+                }
+            }
+            Record::Enemy { id, transform, frames_to_appear, health, velocity, shooter } => {
+                let entity = enemy_pool
+                    .spawn(&mut commands)
+                    .insert_bundle(SpriteBundle {
+                        material: assets.enemy_material.clone(),
+                        transform: *transform,
+                        sprite: Sprite::new(Vec2::new(30.0, 30.0)),
+                        ..Default::default()
+                    })
+                    .insert(Enemy {
+                        frames_to_appear: *frames_to_appear,
+                        health: *health,
+                        velocity: *velocity,
+                    })
+                    .insert(shooter.to_shooter())
+                    .id();
+                enemy_ids.insert(*id, entity);
+            }
+            Record::Boss { id, frames_to_appear, health, max_health, phase, frames, shooter } => {
+                let (boss, parts) = spawn_boss(&mut commands, &arena, &assets, &difficulty);
+                enemy_ids.insert(*id, boss);
+                commands
+                    .entity(boss)
+                    .insert(Enemy {
+                        frames_to_appear: *frames_to_appear,
+                        health: *health,
+                        ..Default::default()
+                    })
+                    .insert(Boss {
+                        max_health: *max_health,
+                        phase: *phase,
+                        frames: *frames,
+                    })
+                    .insert(shooter.to_shooter());
+                //spawn_boss puts the parts at these offsets
+                boss_parts.extend(parts.into_iter().zip([-45.0, 45.0].iter().cloned()));
+            }
+            Record::BossPart { .. } => saved_parts.push(record),
+            Record::Bullet { .. } | Record::Shockwave { .. } => hitting.push(record),
+            Record::EnemyBullet { translation, velocity } => {
+                enemy_bullet_pool
+                    .spawn(&mut commands)
+                    .insert_bundle(SpriteBundle {
+                        material: assets.enemy_bullet_material.clone(),
+                        transform: Transform::from_translation(*translation),
+                        sprite: Sprite::new(Vec2::new(8.0, 8.0)),
+                        ..Default::default()
+                    })
+                    .insert(EnemyBullet { velocity: *velocity });
+            }
+            Record::PowerUp { translation, kind, velocity, frames_to_expire } => {
+                commands
+                    .spawn()
+                    .insert_bundle(SpriteBundle {
+                        material: assets.power_up_material(*kind),
+                        transform: Transform::from_translation(*translation),
+                        sprite: Sprite::new(Vec2::new(14.0, 14.0)),
+                        ..Default::default()
+                    })
+                    .insert(PowerUp {
+                        kind: *kind,
+                        velocity: *velocity,
+                        frames_to_expire: *frames_to_expire,
+                    });
+            }
+        }
+    }
+    for (entity, offset_x) in boss_parts {
+        let saved = saved_parts.iter().find_map(|record| match record {
+            Record::BossPart { id, offset_x: x, frames_to_appear, health, shooter }
+                if *x == offset_x =>
+            {
+                Some((id, frames_to_appear, health, shooter))
+            }
+            _ => None,
+        });
+        match saved {
+            Some((id, frames_to_appear, health, shooter)) => {
+                commands
+                    .entity(entity)
+                    .insert(Enemy {
+                        frames_to_appear: *frames_to_appear,
+                        health: *health,
+                        ..Default::default()
+                    })
+                    .insert(shooter.to_shooter());
+                enemy_ids.insert(*id, entity);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    //enemies destroyed before saving are gone, so are their ids
+    let new_entities = |saved: &[u32]| -> Vec<Entity> {
+        saved.iter().filter_map(|id| enemy_ids.get(id).cloned()).collect()
+    };
+    for record in hitting {
+        match record {
+            Record::Bullet {
+                transform,
+                kind,
+                velocity,
+                damage,
+                piercing,
+                homing,
+                bounces,
+                hit_enemy_ids,
+            } => {
+                bullet_pool
+                    .spawn(&mut commands)
+                    .insert_bundle(SpriteBundle {
+                        material: assets.weapon_material(*kind),
+                        transform: *transform,
+                        sprite: Sprite::new(kind.spec().size),
+                        ..Default::default()
+                    })
+                    .insert(Bullet {
+                        kind: *kind,
+                        velocity: *velocity,
+                        damage: *damage,
+                        piercing: *piercing,
+                        hit_enemies: new_entities(hit_enemy_ids),
+                        homing: *homing,
+                        bounces: *bounces,
+                    });
+            }
+            Record::Shockwave { translation, radius, max_radius, speed, damage, hit_enemy_ids } => {
+                commands
+                    .spawn()
+                    .insert_bundle(SpriteBundle {
+                        material: assets.shockwave_material.clone(),
+                        transform: Transform::from_translation(*translation),
+                        sprite: Sprite::new(Vec2::ZERO),
+                        ..Default::default()
+                    })
+                    .insert(Shockwave {
+                        radius: *radius,
+                        max_radius: *max_radius,
+                        speed: *speed,
+                        damage: *damage,
+                        hit_enemies: new_entities(hit_enemy_ids),
+                    });
+            }
+            _ => {}
+        }
+    }
+    state.pop().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::AssetPlugin;
+    use bevy::ecs::component::Component;
+    use bevy::input::InputPlugin;
+    use bevy::window::{WindowId, WindowPlugin};
+
+    use super::*;
+    use crate::components::DevOptions;
+    use crate::high_score_plugin::HighScoreTable;
+    use crate::settings_plugin::Settings;
+    use crate::{GameConfig, GamePlugins};
+
+    const WIDTH: u32 = 480;
+    const HEIGHT: u32 = 320;
+    //long enough for enemies, their bullets and a few kills
+    const FRAMES_BEFORE_SAVE: u32 = 600;
+    const FRAMES_AFTER_SAVE: u32 = 300;
+    //positions may differ by rounding, the order of the entities isn't the same
+    const EPSILON: f32 = 0.01;
+
+    //InGame frames played, counted the same way in both games, used as Resource
+    #[derive(Default)]
+    struct Frames(u32);
+
+    fn count_frames_system(mut frames: ResMut<Frames>) {
+        frames.0 += 1;
+    }
+
+    //The save file made by save_run_system, used as Resource
+    struct SavedText(String);
+
+    fn save_text_system(run: RunSnapshot, mut text: ResMut<SavedText>) {
+        text.0 = run.to_text();
+    }
+
+    //the gameplay without a window, like the stress binary, resuming the run if given
+    fn headless_game(run: Option<Vec<Record>>) -> App {
+        let descriptor = WindowDescriptor {
+            width: WIDTH as f32,
+            height: HEIGHT as f32,
+            ..Default::default()
+        };
+        let mut app = App::build();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(InputPlugin::default())
+            .add_plugin(WindowPlugin {
+                add_primary_window: false,
+                exit_on_close: false,
+            })
+            .add_asset::<Texture>()
+            .add_asset::<ColorMaterial>()
+            .add_asset::<Font>()
+            .insert_resource(DevOptions {
+                god: true,
+                fire_rate: 1.0,
+                seed: Some(7),
+            })
+            .insert_resource(PendingRun(run))
+            //an empty table that is never saved, GameOverPlugin would load the player's one
+            .insert_resource(HighScoreTable::default());
+        app.world_mut()
+            .get_resource_mut::<Windows>()
+            .unwrap()
+            .add(Window::new(WindowId::primary(), &descriptor, WIDTH, HEIGHT, 1.0, None));
+        let config = GameConfig::new()
+            .gameplay()
+            .save()
+            .initial_state(AppState::InGame)
+            .arena(WIDTH as f32, HEIGHT as f32);
+        app.add_plugins(GamePlugins(config))
+            .init_resource::<Frames>()
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame).with_system(count_frames_system.system()),
+            );
+        //held from the first frame, so both games fire the same
+        let fire = app.world_mut().get_resource::<Settings>().unwrap().keys.fire;
+        app.world_mut().get_resource_mut::<Input<KeyCode>>().unwrap().press(fire);
+        app.app
+    }
+
+    fn play_until(app: &mut App, frames: u32) {
+        while app.world.get_resource::<Frames>().unwrap().0 < frames {
+            app.update();
+        }
+    }
+
+    fn save_text(world: &mut World) -> String {
+        world.insert_resource(SavedText(String::new()));
+        SystemStage::single(save_text_system.system()).run(world);
+        world.remove_resource::<SavedText>().unwrap().0
+    }
+
+    fn positions<T: Component>(world: &mut World) -> Vec<Vec3> {
+        let mut query = world.query_filtered::<(&Transform, &Visible), With<T>>();
+        query
+            .iter(world)
+            .filter(|(_, visible)| visible.is_visible)
+            .map(|(tr, _)| tr.translation)
+            .collect()
+    }
+
+    //every position has one in the other game, near enough
+    fn assert_same_positions(name: &str, expected: Vec<Vec3>, mut actual: Vec<Vec3>) {
+        assert_eq!(expected.len(), actual.len(), "number of {}", name);
+        for position in expected {
+            match actual.iter().position(|other| other.distance(position) < EPSILON) {
+                Some(index) => {
+                    actual.swap_remove(index);
+                }
+                None => panic!("{} at {} is not in the resumed game", name, position),
+            }
+        }
+    }
+
+    fn assert_same_game(expected: &mut World, actual: &mut World) {
+        let score = |world: &World| {
+            let score = world.get_resource::<Score>().unwrap();
+            (score.points, score.wave, world.get_resource::<GameRng>().unwrap().state)
+        };
+        assert_eq!(score(expected), score(actual), "points, wave and random state");
+        let health = |world: &mut World| -> i32 {
+            let mut query = world.query::<(&Enemy, &Visible)>();
+            query
+                .iter(world)
+                .filter(|(_, visible)| visible.is_visible)
+                .map(|(enemy, _)| enemy.health)
+                .sum()
+        };
+        assert_eq!(health(expected), health(actual), "health of all enemies");
+        assert_same_positions(
+            "player",
+            positions::<PlayerShip>(expected),
+            positions::<PlayerShip>(actual),
+        );
+        assert_same_positions("enemy", positions::<Enemy>(expected), positions::<Enemy>(actual));
+        assert_same_positions("bullet", positions::<Bullet>(expected), positions::<Bullet>(actual));
+        assert_same_positions(
+            "enemy bullet",
+            positions::<EnemyBullet>(expected),
+            positions::<EnemyBullet>(actual),
+        );
+    }
+
+    #[test]
+    fn resumed_run_plays_the_same() {
+        let mut original = headless_game(None);
+        play_until(&mut original, FRAMES_BEFORE_SAVE);
+        let text = save_text(&mut original.world);
+        let run = parse(&text).expect("the saved run can be read back");
+
+        //the resumed game counts its frames from the restore
+        let mut resumed = headless_game(Some(run));
+        play_until(&mut resumed, FRAMES_AFTER_SAVE);
+        play_until(&mut original, FRAMES_BEFORE_SAVE + FRAMES_AFTER_SAVE);
+
+        assert_same_game(&mut original.world, &mut resumed.world);
+    }
+
+    #[test]
+    fn broken_files_are_rejected() {
+        let player = "player 0 0 0 0 0 0 1 0 0 3 2 0 0 Spread 0";
+        let header = format!("{} {}", FILE_HEADER, FILE_VERSION);
+        let header = header.as_str();
+        let file = |lines: &[&str]| lines.join("\n") + "\n";
+        assert!(parse(&file(&[header, player])).is_some());
+
+        assert!(parse("").is_none());
+        assert!(parse("garbage\n\u{0}\u{1}").is_none());
+        //another version
+        let older = format!("{} {}", FILE_HEADER, FILE_VERSION - 1);
+        assert!(parse(&file(&[older.as_str(), player])).is_none());
+        //no player to resume with
+        assert!(parse(&file(&[header, "run 7 7 100 2"])).is_none());
+        //cut in the middle of a line
+        assert!(parse(&file(&[header, player, "enemy 3 10 20"])).is_none());
+        //a value too many, or one that isn't a number
+        let longer = format!("{} 1", player);
+        assert!(parse(&file(&[header, longer.as_str()])).is_none());
+        assert!(parse(&file(&[header, "run 7 seven 100 2", player])).is_none());
+        //an unknown record
+        assert!(parse(&file(&[header, player, "dragon 1 2 3"])).is_none());
+        //a list of ids shorter than its count
+        let bullet = "bullet 0 0 0 0 0 0 1 Laser 0 10 1 true false 0 3 1 2";
+        assert!(parse(&file(&[header, player, bullet])).is_none());
+    }
+}
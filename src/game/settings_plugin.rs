@@ -0,0 +1,796 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+
+use crate::audio_plugin::AudioSettings;
+use crate::components::{AppState, Difficulty, DifficultyLevel, SettingsMenu};
+use crate::loading_plugin::GameAssets;
+use crate::menu_plugin::{
+    spawn_menu, take_key, MenuEvent, MenuId, MenuInput, MenuLabel, MenuMaterials, MenuSelection,
+    MENU_NAVIGATION,
+};
+use crate::storage::{data_file, read_file, write_file};
+
+// Plugin
+//Settings screen, pushed on top of the screen it is opened from and popped by Back.
+//Settings are loaded in main() before the window is created, and saved when the screen is closed.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Settings>()
+            .init_resource::<SettingsPage>()
+            .add_system_set(SystemSet::on_enter(AppState::Settings).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::Settings)
+                    //must see the key before MenuPlugin takes the arrows and Enter
+                    .with_system(rebind_key_system.system().before(MENU_NAVIGATION))
+                    .with_system(settings_menu_system.system())
+                    .with_system(settings_label_system.system()),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Settings).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "SettingsPlugin"
+    }
+}
+
+const FILE_NAME: &str = "settings.cfg";
+//written as `version = N`, increased when a field changes meaning.
+//1: first version, 2: no two actions are bound to the same key
+const FILE_VERSION: u32 = 2;
+
+//Window sizes the settings screen cycles through
+const RESOLUTIONS: [(u32, u32); 3] = [(480, 320), (720, 480), (960, 640)];
+//a window size from the file is kept in these bounds, the game can't be played outside them
+const MIN_WIDTH: u32 = 320;
+const MAX_WIDTH: u32 = 3840;
+const MIN_HEIGHT: u32 = 240;
+const MAX_HEIGHT: u32 = 2160;
+
+#[derive(Clone)]
+pub struct VideoSettings {
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+}
+impl Default for VideoSettings {
+    fn default() -> Self {
+        VideoSettings {
+            width: 480,
+            height: 320,
+            fullscreen: false,
+            vsync: true,
+        }
+    }
+}
+impl VideoSettings {
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControlScheme {
+    //the ship follows the cursor, left button fires
+    Mouse,
+    //the ship moves with the bound keys
+    Keyboard,
+}
+
+#[derive(Clone)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub fire: KeyCode,
+    pub bomb: KeyCode,
+    pub next_weapon: KeyCode,
+    pub pause: KeyCode,
+}
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            up: KeyCode::W,
+            down: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+            fire: KeyCode::J,
+            bomb: KeyCode::Space,
+            next_weapon: KeyCode::Tab,
+            pause: KeyCode::P,
+        }
+    }
+}
+impl KeyBindings {
+    //an action bound to the key of an earlier one gets its default key back, or a free one.
+    //false if every key was used once already.
+    fn resolve_conflicts(&mut self) -> bool {
+        let mut defaults = KeyBindings::default();
+        let mut keys: Vec<KeyCode> = (1..BINDING_NAMES.len())
+            .filter_map(|index| binding(self, index).cloned())
+            .collect();
+        let mut changed = false;
+        for i in 0..keys.len() {
+            if !keys[..i].contains(&keys[i]) {
+                continue;
+            }
+            let default = binding(&mut defaults, i + 1).cloned();
+            let free = default
+                .into_iter()
+                .chain(BINDABLE_KEYS.iter().cloned())
+                .find(|key| !keys.contains(key));
+            if let Some(key) = free {
+                keys[i] = key;
+                changed = true;
+            }
+        }
+        for (i, key) in keys.into_iter().enumerate() {
+            if let Some(binding) = binding(self, i + 1) {
+                *binding = key;
+            }
+        }
+        changed
+    }
+}
+
+//Everything saved in settings.cfg, used as Resource
+#[derive(Clone)]
+pub struct Settings {
+    pub video: VideoSettings,
+    pub audio: AudioSettings,
+    pub scheme: ControlScheme,
+    pub keys: KeyBindings,
+    pub difficulty: DifficultyLevel,
+    pub adaptive_difficulty: bool,
+    //None when there is nowhere to save
+    path: Option<PathBuf>,
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            video: VideoSettings::default(),
+            audio: AudioSettings::default(),
+            scheme: ControlScheme::Mouse,
+            keys: KeyBindings::default(),
+            difficulty: DifficultyLevel::Normal,
+            adaptive_difficulty: false,
+            path: None,
+        }
+    }
+}
+
+//Keys that can be bound, also used to read key names from the file
+const BINDABLE_KEYS: [KeyCode; 50] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::Space, KeyCode::Return, KeyCode::Tab, KeyCode::Back,
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl,
+    KeyCode::LAlt, KeyCode::RAlt,
+];
+
+fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.iter().find(|key| key_name(**key) == name).cloned()
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_scheme(value: &str) -> Option<ControlScheme> {
+    match value {
+        "Mouse" => Some(ControlScheme::Mouse),
+        "Keyboard" => Some(ControlScheme::Keyboard),
+        _ => None,
+    }
+}
+
+fn parse_volume(value: &str) -> Option<f32> {
+    value.parse::<f32>().ok().map(|v| v.max(0.0).min(1.0))
+}
+
+impl Settings {
+    //missing file or fields use the defaults, unknown fields are ignored
+    pub fn load() -> Self {
+        let path = data_file(FILE_NAME);
+        let settings = match path.as_ref().and_then(read_file) {
+            Some(text) => Settings::from_text(&text),
+            None => Settings::default(),
+        };
+        Settings { path, ..settings }
+    }
+
+    //the settings in the text of a file, older versions are migrated
+    fn from_text(text: &str) -> Self {
+        let mut settings = Settings::default();
+        let mut version = None;
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(at) => (line[..at].trim(), line[at + 1..].trim()),
+                None => {
+                    warn!("settings: ignored line '{}'", line);
+                    continue;
+                }
+            };
+            if key == "version" {
+                match value.parse::<u32>() {
+                    Ok(v) => version = Some(v),
+                    Err(_) => warn!("settings: ignored version '{}'", value),
+                }
+                continue;
+            }
+            if !settings.set_field(key, value) {
+                warn!("settings: ignored '{} = {}'", key, value);
+            }
+        }
+        //files without it were written before the version was added
+        let version = version.unwrap_or_else(|| {
+            warn!("settings: no version in {}, read as version 1", FILE_NAME);
+            1
+        });
+        if version > FILE_VERSION {
+            warn!(
+                "settings: written by a newer version ({}), unknown fields are ignored",
+                version
+            );
+        }
+        settings.migrate(version);
+        //a file edited by hand can still bind a key twice
+        if settings.keys.resolve_conflicts() {
+            warn!("settings: a key was bound to two actions, one of them got another key");
+        }
+        settings
+    }
+
+    //bring the fields read from an older file up to FILE_VERSION
+    fn migrate(&mut self, version: u32) {
+        if version < 2 && self.keys.resolve_conflicts() {
+            info!("settings: version {} bound a key to two actions, one got another key", version);
+        }
+    }
+
+    //false if the field is unknown or the value can't be read, the default is kept then
+    fn set_field(&mut self, key: &str, value: &str) -> bool {
+        let keys = &mut self.keys;
+        let binding = match key {
+            "key_up" => Some(&mut keys.up),
+            "key_down" => Some(&mut keys.down),
+            "key_left" => Some(&mut keys.left),
+            "key_right" => Some(&mut keys.right),
+            "key_fire" => Some(&mut keys.fire),
+            "key_bomb" => Some(&mut keys.bomb),
+            "key_next_weapon" => Some(&mut keys.next_weapon),
+            "key_pause" => Some(&mut keys.pause),
+            _ => None,
+        };
+        if let Some(binding) = binding {
+            return parse_key(value).map(|key| *binding = key).is_some();
+        }
+        let result = match key {
+            "width" => value
+                .parse::<u32>()
+                .ok()
+                .map(|v| self.video.width = v.max(MIN_WIDTH).min(MAX_WIDTH)),
+            "height" => value
+                .parse::<u32>()
+                .ok()
+                .map(|v| self.video.height = v.max(MIN_HEIGHT).min(MAX_HEIGHT)),
+            "fullscreen" => parse_bool(value).map(|v| self.video.fullscreen = v),
+            "vsync" => parse_bool(value).map(|v| self.video.vsync = v),
+            "audio_enabled" => parse_bool(value).map(|v| self.audio.enabled = v),
+            "master_volume" => parse_volume(value).map(|v| self.audio.master = v),
+            "sfx_volume" => parse_volume(value).map(|v| self.audio.sfx = v),
+            "music_volume" => parse_volume(value).map(|v| self.audio.music = v),
+            "control_scheme" => parse_scheme(value).map(|v| self.scheme = v),
+            "difficulty" => DifficultyLevel::parse(value).map(|v| self.difficulty = v),
+            "adaptive_difficulty" => parse_bool(value).map(|v| self.adaptive_difficulty = v),
+            _ => None,
+        };
+        result.is_some()
+    }
+
+    pub fn save(&self) {
+        let path = match self.path.as_ref() {
+            Some(path) => path,
+            None => return,
+        };
+        write_file(path, &self.to_text());
+    }
+
+    //the content of the file
+    fn to_text(&self) -> String {
+        let keys = &self.keys;
+        let lines = [
+            format!("version = {}", FILE_VERSION),
+            format!("width = {}", self.video.width),
+            format!("height = {}", self.video.height),
+            format!("fullscreen = {}", self.video.fullscreen),
+            format!("vsync = {}", self.video.vsync),
+            format!("audio_enabled = {}", self.audio.enabled),
+            format!("master_volume = {}", self.audio.master),
+            format!("sfx_volume = {}", self.audio.sfx),
+            format!("music_volume = {}", self.audio.music),
+            format!("control_scheme = {:?}", self.scheme),
+            format!("difficulty = {:?}", self.difficulty),
+            format!("adaptive_difficulty = {}", self.adaptive_difficulty),
+            format!("key_up = {}", key_name(keys.up)),
+            format!("key_down = {}", key_name(keys.down)),
+            format!("key_left = {}", key_name(keys.left)),
+            format!("key_right = {}", key_name(keys.right)),
+            format!("key_fire = {}", key_name(keys.fire)),
+            format!("key_bomb = {}", key_name(keys.bomb)),
+            format!("key_next_weapon = {}", key_name(keys.next_weapon)),
+            format!("key_pause = {}", key_name(keys.pause)),
+        ];
+        lines.join("\n") + "\n"
+    }
+}
+
+//The group of settings on screen, used as Resource
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SettingsPage {
+    Main,
+    Audio,
+    Video,
+    Controls,
+    Game,
+    //waiting for a key to bind to the item at this index of the Controls page
+    Rebinding(usize),
+}
+impl Default for SettingsPage {
+    fn default() -> Self {
+        SettingsPage::Main
+    }
+}
+
+//change of a volume for one Left/Right press
+const VOLUME_STEP: f32 = 0.1;
+
+const MAIN_ITEMS: usize = 5;
+const AUDIO_ITEMS: usize = 4;
+const VIDEO_ITEMS: usize = 4;
+const CONTROLS_ITEMS: usize = 10;
+const GAME_ITEMS: usize = 3;
+
+fn page_items(page: SettingsPage) -> usize {
+    match page {
+        SettingsPage::Main => MAIN_ITEMS,
+        SettingsPage::Audio => AUDIO_ITEMS,
+        SettingsPage::Video => VIDEO_ITEMS,
+        SettingsPage::Controls | SettingsPage::Rebinding(_) => CONTROLS_ITEMS,
+        SettingsPage::Game => GAME_ITEMS,
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+//the binding shown at an index of the Controls page, 0 is the scheme
+fn binding(keys: &mut KeyBindings, index: usize) -> Option<&mut KeyCode> {
+    match index {
+        1 => Some(&mut keys.up),
+        2 => Some(&mut keys.down),
+        3 => Some(&mut keys.left),
+        4 => Some(&mut keys.right),
+        5 => Some(&mut keys.fire),
+        6 => Some(&mut keys.bomb),
+        7 => Some(&mut keys.next_weapon),
+        8 => Some(&mut keys.pause),
+        _ => None,
+    }
+}
+
+const BINDING_NAMES: [&str; 9] = ["", "Up", "Down", "Left", "Right", "Fire", "Bomb", "Weapon", "Pause"];
+
+fn label(page: SettingsPage, index: usize, settings: &Settings) -> String {
+    let percent = |volume: f32| (volume * 100.0).round() as i32;
+    if index + 1 >= page_items(page) {
+        return "Back".to_string();
+    }
+    match page {
+        SettingsPage::Main => ["Audio", "Video", "Controls", "Game"][index].to_string(),
+        SettingsPage::Audio => match index {
+            0 => format!("Master: {}%", percent(settings.audio.master)),
+            1 => format!("Effects: {}%", percent(settings.audio.sfx)),
+            _ => format!("Music: {}%", percent(settings.audio.music)),
+        },
+        SettingsPage::Video => match index {
+            0 => format!("Window: {}x{}", settings.video.width, settings.video.height),
+            1 => format!("Fullscreen: {}", on_off(settings.video.fullscreen)),
+            _ => format!("VSync: {}", on_off(settings.video.vsync)),
+        },
+        SettingsPage::Controls | SettingsPage::Rebinding(_) => {
+            if index == 0 {
+                return format!("Controls: {:?}", settings.scheme);
+            }
+            if page == SettingsPage::Rebinding(index) {
+                return format!("{}: press a key", BINDING_NAMES[index]);
+            }
+            let mut keys = settings.keys.clone();
+            let key = *binding(&mut keys, index).unwrap();
+            format!("{}: {}", BINDING_NAMES[index], key_name(key))
+        }
+        SettingsPage::Game => match index {
+            0 => format!("Difficulty: {:?}", settings.difficulty),
+            _ => format!("Adaptive: {}", on_off(settings.adaptive_difficulty)),
+        },
+    }
+}
+
+fn page_title(page: SettingsPage) -> &'static str {
+    match page {
+        SettingsPage::Main => "SETTINGS",
+        SettingsPage::Audio => "AUDIO",
+        SettingsPage::Video => "VIDEO",
+        SettingsPage::Controls | SettingsPage::Rebinding(_) => "CONTROLS",
+        SettingsPage::Game => "GAME",
+    }
+}
+
+fn spawn_page(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    materials: &MenuMaterials,
+    selection: &mut MenuSelection,
+    page: SettingsPage,
+    settings: &Settings,
+) {
+    let items = (0..page_items(page))
+        .map(|index| label(page, index, settings))
+        .collect::<Vec<_>>();
+    let root = spawn_menu(
+        commands,
+        assets,
+        materials,
+        selection,
+        MenuId::Settings,
+        page_title(page),
+        &items,
+    );
+    commands.entity(root).insert(SettingsMenu);
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    materials: Res<MenuMaterials>,
+    mut selection: ResMut<MenuSelection>,
+    mut page: ResMut<SettingsPage>,
+    settings: Res<Settings>,
+) {
+    *page = SettingsPage::Main;
+    spawn_page(&mut commands, &assets, &materials, &mut selection, *page, &settings);
+}
+
+fn cleanup(
+    mut commands: Commands,
+    menus: Query<Entity, With<SettingsMenu>>,
+    settings: Res<Settings>,
+) {
+    for entity in menus.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    settings.save();
+}
+
+//apply the video settings to the window that is already open
+fn apply_video(video: &VideoSettings, windows: &mut Windows) {
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_resolution(video.width as f32, video.height as f32);
+        window.set_vsync(video.vsync);
+        window.set_mode(video.window_mode());
+    }
+}
+
+//Enter or a click opens pages and toggles values, Left/Right change them, Back or Escape return
+fn settings_menu_system(
+    mut commands: Commands,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut events: EventReader<MenuEvent>,
+    mut selection: ResMut<MenuSelection>,
+    mut settings: ResMut<Settings>,
+    mut page: ResMut<SettingsPage>,
+    mut state: ResMut<State<AppState>>,
+    mut windows: ResMut<Windows>,
+    mut difficulty: ResMut<Difficulty>,
+    assets: Res<GameAssets>,
+    materials: Res<MenuMaterials>,
+    menus: Query<Entity, With<SettingsMenu>>,
+) {
+    if let SettingsPage::Rebinding(_) = *page {
+        return;
+    }
+    let input = MenuInput::read(&mut keys, &mut buttons);
+    let chosen = events
+        .iter()
+        .filter(|event| event.menu == MenuId::Settings)
+        .map(|event| event.index)
+        .last();
+    let back = input.back || chosen == Some(page_items(*page) - 1);
+    let mut next_page = *page;
+    if back {
+        if *page == SettingsPage::Main {
+            state.pop().unwrap();
+            return;
+        }
+        next_page = SettingsPage::Main;
+    } else {
+        //-1, 0 (chosen) or 1
+        let step: i32 = if input.left {
+            -1
+        } else if input.right {
+            1
+        } else if chosen.is_some() {
+            0
+        } else {
+            return;
+        };
+        let index = chosen.unwrap_or(selection.0);
+        let current = *page;
+        match current {
+            SettingsPage::Main => {
+                if chosen.is_some() {
+                    next_page = match index {
+                        0 => SettingsPage::Audio,
+                        1 => SettingsPage::Video,
+                        2 => SettingsPage::Controls,
+                        _ => SettingsPage::Game,
+                    };
+                }
+            }
+            SettingsPage::Audio => {
+                let volume = match index {
+                    0 => &mut settings.audio.master,
+                    1 => &mut settings.audio.sfx,
+                    _ => &mut settings.audio.music,
+                };
+                let value = if step == 0 {
+                    //Enter raises the volume, wrapping to 0 after 100%
+                    if *volume >= 0.95 {
+                        0.0
+                    } else {
+                        *volume + VOLUME_STEP
+                    }
+                } else {
+                    *volume + VOLUME_STEP * step as f32
+                };
+                *volume = (value.max(0.0).min(1.0) * 10.0).round() / 10.0;
+            }
+            SettingsPage::Video => {
+                let video = &mut settings.video;
+                match index {
+                    0 => {
+                        let current = RESOLUTIONS
+                            .iter()
+                            .position(|r| *r == (video.width, video.height))
+                            .unwrap_or(0);
+                        let count = RESOLUTIONS.len() as i32;
+                        let next = (current as i32 + if step < 0 { -1 } else { 1 } + count) % count;
+                        let (width, height) = RESOLUTIONS[next as usize];
+                        video.width = width;
+                        video.height = height;
+                    }
+                    1 => video.fullscreen = !video.fullscreen,
+                    _ => video.vsync = !video.vsync,
+                }
+                apply_video(video, &mut windows);
+            }
+            SettingsPage::Controls => {
+                if index == 0 {
+                    settings.scheme = match settings.scheme {
+                        ControlScheme::Mouse => ControlScheme::Keyboard,
+                        ControlScheme::Keyboard => ControlScheme::Mouse,
+                    };
+                } else if chosen.is_some() {
+                    *page = SettingsPage::Rebinding(index);
+                }
+            }
+            //the choice also applies to the current run
+            SettingsPage::Game => {
+                if index == 0 {
+                    settings.difficulty = if step < 0 {
+                        //previous level
+                        settings.difficulty.next().next()
+                    } else {
+                        settings.difficulty.next()
+                    };
+                    difficulty.level = settings.difficulty;
+                } else {
+                    settings.adaptive_difficulty = !settings.adaptive_difficulty;
+                    difficulty.adaptive = settings.adaptive_difficulty;
+                    difficulty.pressure = 1.0;
+                }
+            }
+            SettingsPage::Rebinding(_) => {}
+        }
+    }
+    if next_page != *page {
+        *page = next_page;
+        for entity in menus.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_page(&mut commands, &assets, &materials, &mut selection, *page, &settings);
+    }
+}
+
+//the next key pressed is bound, Escape cancels
+fn rebind_key_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut page: ResMut<SettingsPage>,
+    mut settings: ResMut<Settings>,
+) {
+    let index = match *page {
+        SettingsPage::Rebinding(index) => index,
+        _ => return,
+    };
+    if take_key(&mut keys, KeyCode::Escape) {
+        *page = SettingsPage::Controls;
+        return;
+    }
+    let pressed = keys
+        .get_just_pressed()
+        .find(|key| BINDABLE_KEYS.contains(key))
+        .cloned();
+    if let Some(key) = pressed {
+        keys.reset(key);
+        let old = match binding(&mut settings.keys, index) {
+            Some(binding) => std::mem::replace(binding, key),
+            None => return,
+        };
+        //an action already using the key gets the old one, so no key does two things
+        for other in 1..BINDING_NAMES.len() {
+            if other == index {
+                continue;
+            }
+            if let Some(binding) = binding(&mut settings.keys, other) {
+                if *binding == key {
+                    *binding = old;
+                }
+            }
+        }
+        *page = SettingsPage::Controls;
+    }
+}
+
+fn settings_label_system(
+    settings: Res<Settings>,
+    page: Res<SettingsPage>,
+    mut labels: Query<(&MenuLabel, &mut Text)>,
+) {
+    if !settings.is_changed() && !page.is_changed() {
+        return;
+    }
+    for (label_of, mut text) in labels.iter_mut() {
+        text.sections[0].value = label(*page, label_of.index, &settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_keys(settings: &mut Settings) -> Vec<KeyCode> {
+        (1..BINDING_NAMES.len())
+            .filter_map(|index| binding(&mut settings.keys, index).cloned())
+            .collect()
+    }
+
+    #[test]
+    fn saved_settings_are_read_back() {
+        let mut settings = Settings::default();
+        settings.video.width = 720;
+        settings.video.fullscreen = true;
+        settings.audio.music = 0.5;
+        settings.scheme = ControlScheme::Keyboard;
+        settings.difficulty = DifficultyLevel::Hard;
+        settings.keys.fire = KeyCode::K;
+        let read = Settings::from_text(&settings.to_text());
+        assert_eq!(read.video.width, 720);
+        assert!(read.video.fullscreen);
+        assert_eq!(read.audio.music, 0.5);
+        assert_eq!(read.scheme, ControlScheme::Keyboard);
+        assert_eq!(read.difficulty, DifficultyLevel::Hard);
+        assert_eq!(read.keys.fire, KeyCode::K);
+    }
+
+    #[test]
+    fn missing_fields_use_the_defaults() {
+        let settings = Settings::from_text("version = 2\nwidth = 720\n");
+        let defaults = Settings::default();
+        assert_eq!(settings.video.width, 720);
+        assert_eq!(settings.video.height, defaults.video.height);
+        assert_eq!(settings.audio.master, defaults.audio.master);
+        assert_eq!(settings.keys.up, defaults.keys.up);
+        assert_eq!(settings.difficulty, defaults.difficulty);
+    }
+
+    #[test]
+    fn unknown_fields_and_lines_are_ignored() {
+        let settings = Settings::from_text("version = 2\ncolor = red\nnonsense\nwidth = 720\n");
+        assert_eq!(settings.video.width, 720);
+    }
+
+    #[test]
+    fn bad_values_keep_the_defaults() {
+        let text = "version = 2\nwidth = big\nfullscreen = maybe\nkey_up = Banana\n\
+                    control_scheme = Joystick\ndifficulty = Insane\n";
+        let settings = Settings::from_text(text);
+        let defaults = Settings::default();
+        assert_eq!(settings.video.width, defaults.video.width);
+        assert_eq!(settings.video.fullscreen, defaults.video.fullscreen);
+        assert_eq!(settings.keys.up, defaults.keys.up);
+        assert_eq!(settings.scheme, defaults.scheme);
+        assert_eq!(settings.difficulty, defaults.difficulty);
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped() {
+        let text = "version = 2\nwidth = 99999\nheight = 1\nmaster_volume = 3\nsfx_volume = -1\n";
+        let settings = Settings::from_text(text);
+        assert_eq!(settings.video.width, MAX_WIDTH);
+        assert_eq!(settings.video.height, MIN_HEIGHT);
+        assert_eq!(settings.audio.master, 1.0);
+        assert_eq!(settings.audio.sfx, 0.0);
+    }
+
+    #[test]
+    fn conflicting_bindings_are_resolved() {
+        //fire is J by default, the earlier action keeps the key
+        let mut settings = Settings::from_text("version = 1\nkey_up = J\n");
+        assert_eq!(settings.keys.up, KeyCode::J);
+        assert_ne!(settings.keys.fire, KeyCode::J);
+        let keys = all_keys(&mut settings);
+        for (i, key) in keys.iter().enumerate() {
+            assert!(!keys[..i].contains(key), "{:?} is bound twice", key);
+        }
+    }
+
+    #[test]
+    fn conflicting_binding_gets_its_default_back() {
+        let settings = Settings::from_text("version = 2\nkey_up = Up\nkey_down = Up\n");
+        assert_eq!(settings.keys.up, KeyCode::Up);
+        assert_eq!(settings.keys.down, KeyCode::S);
+    }
+
+    #[test]
+    fn files_without_version_are_read() {
+        let settings = Settings::from_text("width = 720\n");
+        assert_eq!(settings.video.width, 720);
+    }
+
+    #[test]
+    fn newer_versions_are_read_as_far_as_known() {
+        let text = format!("version = {}\nwidth = 720\nnew_field = 1\n", FILE_VERSION + 1);
+        let settings = Settings::from_text(&text);
+        assert_eq!(settings.video.width, 720);
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+//Buckets of points on a uniform grid, to find the points near a position without checking all.
+//Rebuilt every frame, clear() keeps the buckets that were used so a steady crowd allocates nothing.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(usize, Vec2)>>,
+}
+
+impl SpatialGrid {
+    //queries with a radius up to cell_size look at 3x3 cells
+    pub fn new(cell_size: f32) -> Self {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn clear(&mut self) {
+        //cells empty since the last clear are dropped, the others keep their memory
+        self.cells.retain(|_, points| !points.is_empty());
+        for points in self.cells.values_mut() {
+            points.clear();
+        }
+    }
+
+    //index is given back by the queries, e.g. the position in a Vec of the items
+    pub fn insert(&mut self, index: usize, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_insert_with(Vec::new).push((index, position));
+    }
+
+    //calls f with the index, position and distance of every point within radius
+    pub fn for_each_near<F: FnMut(usize, Vec2, f32)>(&self, position: Vec2, radius: f32, mut f: F) {
+        let reach = (radius / self.cell_size).ceil() as i32;
+        let (cx, cy) = self.cell(position);
+        for x in cx - reach..=cx + reach {
+            for y in cy - reach..=cy + reach {
+                let points = match self.cells.get(&(x, y)) {
+                    Some(points) => points,
+                    None => continue,
+                };
+                for (index, point) in points.iter() {
+                    let distance = point.distance(position);
+                    if distance <= radius {
+                        f(*index, *point, distance);
+                    }
+                }
+            }
+        }
+    }
+}
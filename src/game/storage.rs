@@ -0,0 +1,79 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+//folder under the user's data directory
+const APP_DIR: &str = "bevy-game-tutorial";
+
+//Path of a file in the game's data directory, the directory is created if needed.
+//None if the platform has no data directory.
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join(APP_DIR);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!("can't create {}: {}", dir.display(), e);
+        return None;
+    }
+    Some(dir.join(name))
+}
+
+//None if the file doesn't exist yet
+pub fn read_file(path: &PathBuf) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            warn!("can't read {}: {}", path.display(), e);
+            backup_broken_file(path);
+            None
+        }
+    }
+}
+
+//write to a temporary file first so a crash never leaves half a file
+pub fn write_file(path: &PathBuf, text: &str) {
+    let tmp = path.with_extension("tmp");
+    let result = fs::write(&tmp, text).and_then(|_| fs::rename(&tmp, path));
+    if let Err(e) = result {
+        warn!("can't write {}: {}", path.display(), e);
+    }
+}
+
+pub fn remove_file(path: &PathBuf) {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            warn!("can't remove {}: {}", path.display(), e);
+        }
+        _ => {}
+    }
+}
+
+//keep a file that can't be read as <name>.bak, the caller starts again from defaults
+pub fn backup_broken_file(path: &PathBuf) {
+    let backup = path.with_extension("bak");
+    warn!("{} is broken, moved to {}", path.display(), backup.display());
+    if let Err(e) = fs::rename(path, &backup) {
+        warn!("can't move {}: {}", path.display(), e);
+    }
+}
+
+//today as YYYY-MM-DD (UTC)
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    //days since 1970-01-01 to a civil date
+    let z = (seconds / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,102 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::components::{AppState, TimeScale};
+use crate::console_plugin::{ConsoleAppExt, ConsoleCommandEvent, ConsoleCommands, ConsoleLog};
+
+// Plugin
+//Advances TimeScale at the start of each frame in game.
+//F6 toggles the frame-step mode, F7 advances one frame while it's on.
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<TimeScale>()
+            .add_console_command("timescale", "timescale <scale 0-10>")
+            .add_console_command("step", "step [on|off]")
+            .add_system(console_command_system.system())
+            //before the gameplay systems in Update read it
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                time_scale_system.system().after(InputSystem),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup.system()));
+    }
+    fn name(&self) -> &str {
+        "TimeScalePlugin"
+    }
+}
+
+//speed during the slow motion
+const SLOW_MOTION_SCALE: f32 = 0.25;
+//faster makes the frame counters of the systems overflow and the game unplayable anyway
+const MAX_SCALE: f32 = 10.0;
+
+//the console scale and the step mode are kept for the next run
+fn cleanup(
+    mut time: ResMut<TimeScale>,
+) {
+    time.slow_motion_frames = 0;
+    time.fraction = 0.0;
+}
+
+fn time_scale_system(
+    mut time: ResMut<TimeScale>,
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+) {
+    //overlays like Paused stop the game time, slow motion included
+    if state.current() != &AppState::InGame {
+        return;
+    }
+    if keys.just_pressed(KeyCode::F6) {
+        time.stepping = !time.stepping;
+    }
+    time.delta = if time.stepping {
+        if keys.just_pressed(KeyCode::F7) { 1.0 } else { 0.0 }
+    } else if time.slow_motion_frames > 0 {
+        time.slow_motion_frames -= 1;
+        time.scale * SLOW_MOTION_SCALE
+    } else {
+        time.scale
+    };
+    time.fraction += time.delta;
+    time.ticks = time.fraction.floor() as i32;
+    time.fraction -= time.ticks as f32;
+}
+
+fn console_command_system(
+    mut events: EventReader<ConsoleCommandEvent>,
+    mut log: ResMut<ConsoleLog>,
+    console_commands: Res<ConsoleCommands>,
+    mut time: ResMut<TimeScale>,
+) {
+    for event in events.iter() {
+        match event.command {
+            "timescale" => match event.arg::<f32>(0) {
+                Some(scale) if (0.0..=MAX_SCALE).contains(&scale) => {
+                    time.scale = scale;
+                    log.print(format!("timescale {}", scale));
+                }
+                _ => log.usage(&console_commands, event),
+            },
+            "step" => {
+                time.stepping = match event.args.get(0).map(|arg| arg.as_str()) {
+                    None => !time.stepping,
+                    Some("on") => true,
+                    Some("off") => false,
+                    Some(_) => {
+                        log.usage(&console_commands, event);
+                        continue;
+                    }
+                };
+                log.print(if time.stepping {
+                    "frame step on, F7 advances one frame"
+                } else {
+                    "frame step off"
+                });
+            }
+            _ => {}
+        }
+    }
+}
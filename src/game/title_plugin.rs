@@ -0,0 +1,261 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::components::{AppState, AttractMode, DemoText, Enemy, PlayerShip, TitleScreen};
+use crate::loading_plugin::GameAssets;
+use crate::menu_plugin::{spawn_menu, MenuEvent, MenuId, MenuMaterials, MenuSelection};
+use crate::save_plugin::{saved_run_exists, take_saved_run, PendingRun};
+
+// Plugin
+//Title screen with the main menu. Left idle, it starts a demo game (attract mode).
+pub struct TitlePlugin;
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<AttractMode>()
+            .init_resource::<TitleMenu>()
+            .add_system_set(SystemSet::on_enter(AppState::Title).with_system(setup.system()))
+            //the menu is removed while Settings or HighScores are on top of it
+            .add_system_set(SystemSet::on_resume(AppState::Title).with_system(setup.system()))
+            .add_system_set(
+                SystemSet::on_update(AppState::Title).with_system(title_menu_system.system()),
+            )
+            .add_system_set(SystemSet::on_pause(AppState::Title).with_system(cleanup.system()))
+            .add_system_set(SystemSet::on_exit(AppState::Title).with_system(cleanup.system()))
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame).with_system(setup_demo.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(demo_pilot_system.system())
+                    .with_system(demo_exit_system.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame).with_system(cleanup_demo.system()),
+            );
+    }
+    fn name(&self) -> &str {
+        "TitlePlugin"
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TitleAction {
+    Continue,
+    Play,
+    Settings,
+    HighScores,
+    Quit,
+}
+
+//Action of each item of the menu on screen, used as Resource.
+//Continue is only there when a run was saved.
+#[derive(Default)]
+struct TitleMenu(Vec<TitleAction>);
+
+//frames without input before the demo starts
+const IDLE_FRAMES: i32 = 600;
+//length of the demo
+const DEMO_FRAMES: i32 = 1800;
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    materials: Res<MenuMaterials>,
+    mut selection: ResMut<MenuSelection>,
+    mut attract: ResMut<AttractMode>,
+    descriptor: Res<WindowDescriptor>,
+    mut menu: ResMut<TitleMenu>,
+) {
+    attract.frames = 0;
+    menu.0 = vec![
+        TitleAction::Play,
+        TitleAction::Settings,
+        TitleAction::HighScores,
+        TitleAction::Quit,
+    ];
+    if saved_run_exists() {
+        menu.0.insert(0, TitleAction::Continue);
+    }
+    let items = menu
+        .0
+        .iter()
+        .map(|action| match action {
+            TitleAction::Continue => "Continue",
+            TitleAction::Play => "Play",
+            TitleAction::Settings => "Settings",
+            TitleAction::HighScores => "High Scores",
+            TitleAction::Quit => "Quit",
+        })
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>();
+    let root = spawn_menu(
+        &mut commands,
+        &assets,
+        &materials,
+        &mut selection,
+        MenuId::Title,
+        &descriptor.title,
+        &items,
+    );
+    commands.entity(root).insert(TitleScreen);
+}
+
+fn cleanup(
+    mut commands: Commands,
+    screens: Query<Entity, With<TitleScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn title_menu_system(
+    mut events: EventReader<MenuEvent>,
+    mut cursor_events: EventReader<CursorMoved>,
+    selection: Res<MenuSelection>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut attract: ResMut<AttractMode>,
+    mut state: ResMut<State<AppState>>,
+    mut exit: EventWriter<AppExit>,
+    menu: Res<TitleMenu>,
+    mut pending: ResMut<PendingRun>,
+) {
+    for event in events.iter() {
+        if event.menu != MenuId::Title {
+            continue;
+        }
+        match menu.0.get(event.index) {
+            Some(TitleAction::Continue) => {
+                //a broken save starts a new game
+                pending.0 = take_saved_run();
+                attract.active = false;
+                state.set(AppState::InGame).unwrap();
+            }
+            Some(TitleAction::Play) => {
+                attract.active = false;
+                state.set(AppState::InGame).unwrap();
+            }
+            Some(TitleAction::Settings) => state.push(AppState::Settings).unwrap(),
+            Some(TitleAction::HighScores) => state.push(AppState::HighScores).unwrap(),
+            Some(TitleAction::Quit) => exit.send(AppExit),
+            None => {}
+        }
+        return;
+    }
+
+    //start the demo if nobody touches anything
+    let touched = selection.is_changed()
+        || cursor_events.iter().next().is_some()
+        || keys.get_pressed().next().is_some()
+        || mouse.get_pressed().next().is_some()
+        || buttons.get_pressed().next().is_some();
+    if touched {
+        attract.frames = 0;
+        return;
+    }
+    attract.frames += 1;
+    if attract.frames >= IDLE_FRAMES {
+        attract.active = true;
+        attract.frames = 0;
+        state.set(AppState::InGame).unwrap();
+    }
+}
+
+fn setup_demo(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    attract: Res<AttractMode>,
+) {
+    if !attract.active {
+        return;
+    }
+    commands
+        .spawn()
+        .insert_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "DEMO - press any key",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 14.0,
+                    color: Color::YELLOW,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(DemoText);
+}
+
+fn cleanup_demo(
+    mut commands: Commands,
+    texts: Query<Entity, With<DemoText>>,
+    mut attract: ResMut<AttractMode>,
+) {
+    attract.active = false;
+    attract.frames = 0;
+    for entity in texts.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+//fly a figure eight, moving away from enemies that get too close
+fn demo_pilot_system(
+    attract: Res<AttractMode>,
+    mut player: Query<(&Transform, &mut PlayerShip)>,
+    enemies: Query<(&Transform, &Visible), With<Enemy>>,
+    windows: Res<Windows>,
+) {
+    if !attract.active {
+        return;
+    }
+    let window = windows.iter().next().unwrap();
+    let t = attract.frames as f32 * 0.01;
+    let mut target = Vec2::new(t.sin() * window.width() / 3., (t * 2.).sin() * window.height() / 4.);
+    for (tr, mut ship) in player.iter_mut() {
+        let pos = Vec2::new(tr.translation.x, tr.translation.y);
+        for (enemy_tr, visible) in enemies.iter() {
+            if !visible.is_visible {
+                continue;
+            }
+            let away = pos - Vec2::new(enemy_tr.translation.x, enemy_tr.translation.y);
+            if away.length() < 60. && away.length() > 0. {
+                target = pos + away.normalize() * 60.;
+            }
+        }
+        ship.target_x = target.x;
+        ship.target_y = target.y;
+    }
+}
+
+//any input or the end of the demo returns to the title screen
+fn demo_exit_system(
+    mut attract: ResMut<AttractMode>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if !attract.active {
+        return;
+    }
+    attract.frames += 1;
+    let pressed = keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || buttons.get_just_pressed().next().is_some();
+    //the demo ship may die in the same frame, then the game over check already goes to the title
+    if pressed || attract.frames >= DEMO_FRAMES {
+        let _ = state.set(AppState::Title);
+    }
+}
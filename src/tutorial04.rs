@@ -1,10 +1,6 @@
 use bevy::prelude::*;
 
-#[derive(Default)]
-struct PlayerShip {
-    target_x: f32,
-    target_y: f32
-}
+use game::components::PlayerShip;
 
 //
 fn setup(
@@ -1,11 +1,8 @@
 use bevy::prelude::*;
 
-#[derive(Default)]
-struct PlayerShip {
-    target_x: f32,
-    target_y: f32,
-}
+use game::components::PlayerShip;
 
+//the library's bullets come with weapons and pools, this chapter starts simple
 struct Bullet;
 
 #[derive(Default)]
@@ -13,7 +10,6 @@ struct GunState {
     frames_to_fire: i32,
 }
 
-
 //
 fn setup(
     mut commands: Commands,
@@ -1,14 +0,0 @@
-
-#[derive(Default)]
-pub struct PlayerShip {
-    pub target_x: f32,
-    pub target_y: f32,
-}
-
-pub struct Bullet;
-
-#[derive(Default)]
-pub struct GunState {
-    pub frames_to_fire: i32
-}
-
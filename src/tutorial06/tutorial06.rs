@@ -1,11 +1,6 @@
 use bevy::prelude::*;
 
-mod player_ship_plugin;
-mod bullet_plugin;
-mod components;
-
-use player_ship_plugin::PlayerShipPlugin;
-use bullet_plugin::BulletPlugin;
+use game::{GameConfig, GamePlugins};
 
 //
 fn setup(
@@ -14,10 +9,18 @@ fn setup(
     commands
         .spawn()
         .insert_bundle(OrthographicCameraBundle::new_2d());
+    //for the loading screen
+    commands
+        .spawn()
+        .insert_bundle(UiCameraBundle::default());
 }
 
 //
 fn main() {
+    //only the plugins of this chapter, the ship and its bullets
+    let game = GameConfig::new()
+        .player_ship()
+        .bullets();
     App::build()
         .insert_resource(WindowDescriptor {
             title: "Game Title".to_string(),
@@ -27,8 +30,7 @@ fn main() {
             ..Default::default()
         })
         .add_plugins(DefaultPlugins)
-        .add_plugin(PlayerShipPlugin)
-        .add_plugin(BulletPlugin)
+        .add_plugins(GamePlugins(game))
         .add_startup_system(setup.system())
         .run();
 }
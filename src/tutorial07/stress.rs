@@ -0,0 +1,326 @@
+//Headless benchmark of the tutorial07 gameplay.
+//A bot plays while the number of enemies and bullets is kept at the given counts,
+//and the speed of the loop is reported at the end.
+//
+//  cargo run --release --bin stress -- --enemies 10000 --bullets 1000 --ticks 600
+//
+//--profile <file> also writes the timings of the gameplay systems (.json for Chrome trace, or CSV)
+//
+//The game is the same library tutorial07 runs, without its screens, audio and dev tools.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use bevy::app::AppExit;
+use bevy::asset::AssetPlugin;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::window::{WindowId, WindowPlugin};
+
+use game::enemy_plugin::spawn_enemy;
+use game::pool::Pool;
+use game::loading_plugin::GameAssets;
+use game::components::{
+    AppState, Arena, Bullet, CollisionStats, DevOptions, Difficulty, Enemy, EnemyBullet, GameRng,
+    PlayerShip, WeaponKind,
+};
+use game::profiler_plugin::Profiler;
+use game::settings_plugin::Settings;
+use game::{GameConfig, GamePlugins};
+
+//Counts the bytes allocated by the whole program to report the peak
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+fn add_allocated(size: usize) {
+    let now = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_ALLOCATED.fetch_max(now, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = std::alloc::System.alloc(layout);
+        if !ptr.is_null() {
+            add_allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        std::alloc::System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = std::alloc::System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+            add_allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+//Command line options, used as Resource
+struct StressConfig {
+    enemies: usize,
+    bullets: usize,
+    ticks: u32,
+    seed: u64,
+    width: u32,
+    height: u32,
+    profile: Option<PathBuf>,
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        StressConfig {
+            enemies: 1000,
+            bullets: 200,
+            ticks: 600,
+            seed: 1,
+            width: 800,
+            height: 600,
+            profile: None,
+        }
+    }
+}
+
+impl StressConfig {
+    fn from_args() -> Result<Self, String> {
+        let mut config = StressConfig::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value", arg))?;
+            let invalid = |_| format!("invalid value for {}: {}", arg, value);
+            match arg.as_str() {
+                "--enemies" => config.enemies = value.parse().map_err(invalid)?,
+                "--bullets" => config.bullets = value.parse().map_err(invalid)?,
+                "--ticks" => config.ticks = value.parse().map_err(invalid)?,
+                "--seed" => config.seed = value.parse().map_err(invalid)?,
+                "--width" => config.width = value.parse().map_err(invalid)?,
+                "--height" => config.height = value.parse().map_err(invalid)?,
+                "--profile" => config.profile = Some(PathBuf::from(value)),
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+//Progress of the run, used as Resource
+#[derive(Default)]
+struct StressState {
+    ticks: u32,
+    //set on the first tick, so startup is not measured
+    started: Option<Instant>,
+}
+
+//fly in a circle, keep away from the nearest enemy and hold the fire key
+fn bot_system(
+    mut frames: Local<u32>,
+    mut keys: ResMut<Input<KeyCode>>,
+    settings: Res<Settings>,
+    mut player: Query<(&Transform, &mut PlayerShip)>,
+    enemies: Query<(&Transform, &Visible), With<Enemy>>,
+) {
+    *frames += 1;
+    keys.press(settings.keys.fire);
+    let t = *frames as f32 * 0.02;
+    for (tr, mut ship) in player.iter_mut() {
+        let pos = Vec2::new(tr.translation.x, tr.translation.y);
+        let nearest = enemies
+            .iter()
+            .filter(|(_, visible)| visible.is_visible)
+            .map(|(e, _)| Vec2::new(e.translation.x, e.translation.y))
+            .min_by(|a, b| {
+                a.distance_squared(pos)
+                    .partial_cmp(&b.distance_squared(pos))
+                    .unwrap()
+            });
+        let mut target = Vec2::new(t.cos(), t.sin()) * 150.;
+        if let Some(enemy) = nearest {
+            let away = pos - enemy;
+            if away.length() < 60. && away.length() > 0. {
+                target = pos + away.normalize() * 60.;
+            }
+        }
+        ship.target_x = target.x;
+        ship.target_y = target.y;
+    }
+}
+
+//top up enemies and bullets killed or gone out of the arena
+fn keep_load_system(
+    mut commands: Commands,
+    config: Res<StressConfig>,
+    arena: Res<Arena>,
+    mut enemy_pool: ResMut<Pool<Enemy>>,
+    mut bullet_pool: ResMut<Pool<Bullet>>,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+    difficulty: Res<Difficulty>,
+) {
+    let w = arena.width;
+    let h = arena.height;
+    for _ in enemy_pool.live_count()..config.enemies {
+        let position = Vec3::new(rng.next_f32() * w - w / 2., rng.next_f32() * h - h / 2., 0.);
+        spawn_enemy(
+            &mut commands,
+            &mut enemy_pool,
+            &assets,
+            &difficulty,
+            Transform::from_translation(position),
+            None,
+        );
+    }
+    //bouncing bullets stay in the arena
+    let kind = WeaponKind::Bouncing;
+    let spec = kind.spec();
+    for _ in bullet_pool.live_count()..config.bullets {
+        let angle = rng.next_f32() * std::f32::consts::PI * 2.;
+        let position = Vec3::new(rng.next_f32() * w - w / 2., rng.next_f32() * h - h / 2., 0.);
+        bullet_pool
+            .spawn(&mut commands)
+            .insert_bundle(SpriteBundle {
+                material: assets.weapon_material(kind),
+                transform: Transform::from_translation(position),
+                sprite: Sprite::new(spec.size),
+                ..Default::default()
+            })
+            .insert(Bullet {
+                kind,
+                velocity: Vec2::new(angle.cos(), angle.sin()) * spec.speed,
+                damage: spec.damage,
+                piercing: false,
+                hit_enemies: Vec::new(),
+                homing: false,
+                bounces: i32::MAX,
+            });
+    }
+}
+
+fn report_system(
+    config: Res<StressConfig>,
+    mut state: ResMut<StressState>,
+    collisions: Res<CollisionStats>,
+    profiler: Option<Res<Profiler>>,
+    enemies: Res<Pool<Enemy>>,
+    bullets: Res<Pool<Bullet>>,
+    enemy_bullets: Res<Pool<EnemyBullet>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let started = *state.started.get_or_insert_with(Instant::now);
+    state.ticks += 1;
+    if state.ticks < config.ticks {
+        return;
+    }
+    let seconds = started.elapsed().as_secs_f64();
+    let ticks = state.ticks as f64;
+    println!("enemies {}, bullets {}, seed {}", config.enemies, config.bullets, config.seed);
+    println!("ticks: {} in {:.2} s, {:.1} ticks/s", state.ticks, seconds, ticks / seconds);
+    println!(
+        "collision checks: {} total, {:.0} per tick",
+        collisions.checks,
+        collisions.checks as f64 / ticks
+    );
+    println!(
+        "peak memory: {:.1} MiB",
+        PEAK_ALLOCATED.load(Ordering::Relaxed) as f64 / (1024. * 1024.)
+    );
+    println!(
+        "live at the end: enemies {}, bullets {}, enemy bullets {}",
+        enemies.live_count(),
+        bullets.live_count(),
+        enemy_bullets.live_count()
+    );
+    if let (Some(path), Some(profiler)) = (&config.profile, profiler) {
+        let is_trace = path.extension().map_or(false, |ext| ext == "json");
+        let result = if is_trace {
+            profiler.write_chrome_trace(path)
+        } else {
+            profiler.write_csv(path)
+        };
+        match result {
+            Ok(()) => println!("profile written to {}", path.display()),
+            Err(e) => eprintln!("can't write {}: {}", path.display(), e),
+        }
+    }
+    exit.send(AppExit);
+}
+
+fn main() {
+    let config = match StressConfig::from_args() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!(
+                "usage: stress [--enemies N] [--bullets N] [--ticks N] [--seed N] \
+                 [--width N] [--height N] [--profile FILE]"
+            );
+            std::process::exit(2);
+        }
+    };
+    let descriptor = WindowDescriptor {
+        width: config.width as f32,
+        height: config.height as f32,
+        ..Default::default()
+    };
+    let mut app = App::build();
+    //no window, renderer or audio: the loop runs as fast as it can
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(InputPlugin::default())
+        .add_plugin(WindowPlugin {
+            add_primary_window: false,
+            exit_on_close: false,
+        })
+        .add_asset::<Texture>()
+        .add_asset::<ColorMaterial>()
+        .add_asset::<Font>()
+        //the ship can't die and every run is the same
+        .insert_resource(DevOptions {
+            god: true,
+            fire_rate: 1.0,
+            seed: Some(config.seed),
+        });
+    //the ship follows the cursor in the primary window, there must be one
+    app.world_mut()
+        .get_resource_mut::<Windows>()
+        .unwrap()
+        .add(Window::new(
+            WindowId::primary(),
+            &descriptor,
+            config.width,
+            config.height,
+            1.0,
+            None,
+        ));
+    let mut game = GameConfig::new()
+        .gameplay()
+        .initial_state(AppState::InGame)
+        .arena(config.width as f32, config.height as f32);
+    if config.profile.is_some() {
+        game = game.profiler();
+    }
+    app.add_plugins(GamePlugins(game))
+        .insert_resource(descriptor)
+        .insert_resource(config)
+        .init_resource::<StressState>()
+        .add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(bot_system.system())
+                .with_system(keep_load_system.system()),
+        )
+        .add_system_to_stage(CoreStage::Last, report_system.system())
+        .run();
+}
@@ -1,15 +1,8 @@
 use bevy::prelude::*;
 
-mod player_ship_plugin;
-mod bullet_plugin;
-mod components;
-mod enemy_plugin;
-mod collision_plugin;
-
-use player_ship_plugin::PlayerShipPlugin;
-use bullet_plugin::BulletPlugin;
-use crate::enemy_plugin::EnemyPlugin;
-use crate::collision_plugin::CollisionPlugin;
+use game::audio_plugin::audio_device_available;
+use game::settings_plugin::Settings;
+use game::{GameConfig, GamePlugins};
 
 //
 fn setup(
@@ -18,23 +11,41 @@ fn setup(
     commands
         .spawn()
         .insert_bundle(OrthographicCameraBundle::new_2d());
+    //for HUD texts
+    commands
+        .spawn()
+        .insert_bundle(UiCameraBundle::default());
 }
 
 //
 fn main() {
-    App::build()
-        .insert_resource(WindowDescriptor {
-            title: "Game Title".to_string(),
-            width: 480.0,
-            height: 320.0,
-            resizable: false,
-            ..Default::default()
-        })
-        .add_plugins(DefaultPlugins)
-        .add_plugin(PlayerShipPlugin)
-        .add_plugin(BulletPlugin)
-        .add_plugin(EnemyPlugin)
-        .add_plugin(CollisionPlugin)
+    //read before creating the window, it has the window size
+    let settings = Settings::load();
+    let mut app = App::build();
+    app.insert_resource(WindowDescriptor {
+        title: "Game Title".to_string(),
+        width: settings.video.width as f32,
+        height: settings.video.height as f32,
+        vsync: settings.video.vsync,
+        mode: settings.video.window_mode(),
+        resizable: false,
+        ..Default::default()
+    });
+    //bevy's AudioPlugin panics without an audio device (e.g. on CI), run silently instead
+    if audio_device_available(&settings.audio) {
+        app.add_plugins(DefaultPlugins);
+    } else {
+        app.add_plugins_with(DefaultPlugins, |group| {
+            group.disable::<bevy::audio::AudioPlugin>()
+        });
+    }
+    //the difficulty the player chose last time
+    let mut game = GameConfig::full().difficulty(settings.difficulty);
+    if settings.adaptive_difficulty {
+        game = game.adaptive_difficulty();
+    }
+    app.insert_resource(settings)
+        .add_plugins(GamePlugins(game))
         .add_startup_system(setup.system())
         .run();
 }
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use game::components::{AppState, EnemyKilledEvent, PlayerShip, Score, TimeScale};
+use game::loading_plugin::GameAssets;
+use game::{GameConfig, GamePlugins};
+
+//Best score of all plays and the wait before restarting, used as Resource
+#[derive(Default)]
+struct GameOverState {
+    best: u32,
+    //when this==0, a click restarts the game.
+    frames_to_restart: i32,
+}
+
+struct ScoreText;
+struct GameOverText;
+
+const POINTS_PER_ENEMY: u32 = 10;
+
+//
+fn setup(
+    mut commands: Commands,
+) {
+    commands
+        .spawn()
+        .insert_bundle(OrthographicCameraBundle::new_2d());
+    //texts are drawn by the UI camera
+    commands
+        .spawn()
+        .insert_bundle(UiCameraBundle::default());
+}
+
+//the score is shown in every scene after loading, so it's spawned once
+fn spawn_score_text(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+) {
+    commands
+        .spawn()
+        .insert_bundle(TextBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(4.0),
+                    left: Val::Px(4.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }).insert(
+        ScoreText
+    );
+}
+
+//InGame scene
+
+//the plugins spawn the ship and reset the enemies, only the score is ours
+fn start_game(
+    mut score: ResMut<Score>,
+) {
+    score.points = 0;
+}
+
+fn score_system(
+    mut score: ResMut<Score>,
+    mut events: EventReader<EnemyKilledEvent>,
+) {
+    score.points += events.iter().count() as u32 * POINTS_PER_ENEMY;
+}
+
+fn game_over_check_system(
+    mut state: ResMut<State<AppState>>,
+    player: Query<&PlayerShip>,
+    time: Res<TimeScale>,
+) {
+    //the last hit is shown in slow motion first
+    if time.slow_motion_frames > 0 {
+        return;
+    }
+    if player.iter().any(|ship| ship.lives <= 0) {
+        //no lives left, go to GameOver Scene
+        state.set(AppState::GameOver).unwrap();
+    }
+}
+
+//GameOver scene
+
+fn enter_game_over(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    score: Res<Score>,
+    mut game_over: ResMut<GameOverState>,
+) {
+    game_over.best = game_over.best.max(score.points);
+    //wait a little, not to restart by the click which was firing bullets
+    game_over.frames_to_restart = 60;
+    commands
+        .spawn()
+        .insert_bundle(Text2dBundle {
+            text: Text::with_section(
+                format!("GAME OVER\nscore: {}\n\nclick to restart", score.points),
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            ..Default::default()
+        }).insert(
+        GameOverText
+    );
+}
+
+fn restart_system(
+    mut state: ResMut<State<AppState>>,
+    mut game_over: ResMut<GameOverState>,
+    input: Res<Input<MouseButton>>,
+) {
+    if game_over.frames_to_restart > 0 {
+        game_over.frames_to_restart -= 1;
+        return;
+    }
+    if input.just_pressed(MouseButton::Left) {
+        state.set(AppState::InGame).unwrap();
+    }
+}
+
+fn exit_game_over(
+    mut commands: Commands,
+    texts: Query<Entity, With<GameOverText>>,
+) {
+    for entity in texts.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+//runs in every scene
+fn score_text_system(
+    score: Res<Score>,
+    game_over: Res<GameOverState>,
+    mut texts: Query<&mut Text, With<ScoreText>>,
+) {
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format!("score: {}  best: {}", score.points, game_over.best);
+    }
+}
+
+//
+fn main() {
+    //the game of the last chapter, without the library's game over part. This chapter writes it.
+    let game = GameConfig::new()
+        .player_ship()
+        .bullets()
+        .enemies()
+        .collision()
+        .pickups()
+        .particles();
+    App::build()
+        .insert_resource(WindowDescriptor {
+            title: "Game Title".to_string(),
+            width: 480.0,
+            height: 320.0,
+            resizable: false,
+            ..Default::default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_plugins(GamePlugins(game))
+        .insert_resource(GameOverState::default())
+        .add_startup_system(setup.system())
+        .add_system(score_text_system.system())
+        .add_system_set(
+            SystemSet::on_exit(AppState::Loading).with_system(spawn_score_text.system()),
+        )
+        .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(start_game.system()))
+        .add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(score_system.system())
+                .with_system(game_over_check_system.system())
+        )
+        .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(enter_game_over.system()))
+        .add_system_set(SystemSet::on_update(AppState::GameOver).with_system(restart_system.system()))
+        .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(exit_game_over.system()))
+        .run();
+}